@@ -16,3 +16,84 @@ pub const CASH_TOKEN_SEED: &[u8] = b"f"; // cash_token
 #[constant]
 pub const SCASH_TOKEN_SEED: &[u8] = b"g"; // scash_token
 
+#[constant]
+pub const RATIO_PRECISION: u64 = 10000; // 100%
+
+#[constant]
+pub const MAX_LIQUIDITY_FEE: u16 = 1000; // 10%
+
+#[constant]
+pub const INDEX_PRECISION: u128 = 1_000_000_000_000; // borrow_index 1.0
+
+#[constant]
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+#[constant]
+pub const DEFAULT_KINK: u64 = 8000; // 80%
+
+#[constant]
+pub const MAX_BORROW_RATE_BPS: u64 = 100_000; // 利用率 100% 时年化利率上限 1000%
+
+#[constant]
+pub const DEFAULT_LIQUIDATION_BONUS: u64 = 500; // 5%
+
+#[constant]
+pub const DEFAULT_CLOSE_FACTOR: u64 = 5000; // 50%
+
+#[constant]
+pub const LENDER_POSITION_SEED: &[u8] = b"h"; // lender_position
+
+#[cfg(feature = "mock-oracle")]
+#[constant]
+pub const MOCK_PRICE_FEED_SEED: &[u8] = b"i"; // mock_price_feed
+
+#[constant]
+pub const PROTOCOL_FEE_SEED: &[u8] = b"j"; // protocol_fee
+
+#[constant]
+pub const PAUSE_DEPOSITS: u8 = 1 << 0;
+
+#[constant]
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 1;
+
+#[constant]
+pub const PAUSE_MINTING: u8 = 1 << 2;
+
+#[constant]
+pub const PAUSE_ALL: u8 = PAUSE_DEPOSITS | PAUSE_WITHDRAWALS | PAUSE_MINTING;
+
+#[constant]
+pub const CASH_DECIMALS: u8 = 6;
+
+#[constant]
+pub const PRICE_PRECISION: u128 = 1_000_000_000_000; // oracle price 1.0
+
+#[constant]
+pub const DEFAULT_FLASH_LOAN_FEE: u64 = 9; // 0.09%
+
+#[constant]
+pub const MAX_FLASH_LOAN_FEE: u64 = 1000; // 10%
+
+#[constant]
+pub const SWAP_POOL_SEED: &[u8] = b"k"; // swap_pool
+
+#[constant]
+pub const LP_TOKEN_SEED: &[u8] = b"l"; // lp_token
+
+#[constant]
+pub const MINIMUM_LIQUIDITY: u64 = 1000; // 首次注入时永久锁定的 LP 数量
+
+#[constant]
+pub const MIN_AMP: u64 = 1;
+
+#[constant]
+pub const MAX_AMP: u64 = 1_000_000;
+
+#[constant]
+pub const MAX_AMP_CHANGE: u64 = 10; // 单次 ramp 最多放大或缩小 10 倍
+
+#[constant]
+pub const MIN_RAMP_DURATION: i64 = 86400; // 1 day
+
+#[constant]
+pub const MAX_PSM_FEE: u64 = 500; // 5%
@@ -0,0 +1,428 @@
+use anchor_lang::prelude::*;
+use crate::{constants::MINIMUM_LIQUIDITY, errors::FallError};
+
+/// 恒定乘积: 扣除手续费后的输入 dx 换出 dy = y * dx / (x + dx), rounded down
+pub fn constant_product_swap(amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, FallError::InsufficientLiquidity);
+    let numerator = (reserve_out as u128)
+        .checked_mul(amount_in as u128)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    let denominator = (reserve_in as u128)
+        .checked_add(amount_in as u128)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    u64::try_from(numerator / denominator).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// x * y 不能因交易而减少
+pub fn check_constant_product(
+    old_reserve_in: u64,
+    old_reserve_out: u64,
+    new_reserve_in: u64,
+    new_reserve_out: u64,
+) -> Result<()> {
+    let old_k = (old_reserve_in as u128) * (old_reserve_out as u128);
+    let new_k = (new_reserve_in as u128) * (new_reserve_out as u128);
+    require!(new_k >= old_k, FallError::InvariantViolated);
+    Ok(())
+}
+
+/// 计入永久锁定部分的 LP 总量
+fn effective_supply(lp_supply: u64) -> u128 {
+    lp_supply as u128 + MINIMUM_LIQUIDITY as u128
+}
+
+/// 按当前储备比例计算实际注入数量, 不超过传入的上限, rounded up
+pub fn deposit_amounts(
+    max_amount_a: u64,
+    max_amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Result<(u64, u64)> {
+    if lp_supply == 0 {
+        return Ok((max_amount_a, max_amount_b));
+    }
+    require!(reserve_a > 0 && reserve_b > 0, FallError::InsufficientLiquidity);
+
+    let amount_b = (max_amount_a as u128 * reserve_b as u128).div_ceil(reserve_a as u128);
+    if amount_b <= max_amount_b as u128 {
+        return Ok((max_amount_a, amount_b as u64));
+    }
+    let amount_a = (max_amount_b as u128 * reserve_a as u128).div_ceil(reserve_b as u128);
+    require!(amount_a <= max_amount_a as u128, FallError::CalculationError);
+    Ok((amount_a as u64, max_amount_b))
+}
+
+/// 注入后应铸造的 LP 数量, rounded down. 首次注入为 sqrt(a * b) 扣除锁定部分
+pub fn liquidity_for_deposit(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Result<u64> {
+    let liquidity = if lp_supply == 0 {
+        let initial = sqrt((amount_a as u128) * (amount_b as u128));
+        require!(initial > MINIMUM_LIQUIDITY as u128, FallError::DepositTooSmall);
+        initial - MINIMUM_LIQUIDITY as u128
+    } else {
+        let supply = effective_supply(lp_supply);
+        let by_a = amount_a as u128 * supply / reserve_a as u128;
+        let by_b = amount_b as u128 * supply / reserve_b as u128;
+        by_a.min(by_b)
+    };
+    u64::try_from(liquidity).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// 赎回 LP 可取回的 token a / token b 数量, rounded down
+pub fn withdraw_amounts(
+    lp_amount: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Result<(u64, u64)> {
+    let supply = effective_supply(lp_supply);
+    let amount_a = lp_amount as u128 * reserve_a as u128 / supply;
+    let amount_b = lp_amount as u128 * reserve_b as u128 / supply;
+    Ok((
+        u64::try_from(amount_a).map_err(|_| error!(FallError::NumberOverflow))?,
+        u64::try_from(amount_b).map_err(|_| error!(FallError::NumberOverflow))?,
+    ))
+}
+
+/// 整数平方根, rounded down
+fn sqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// StableSwap 固定为两种 token
+const N_COINS: u128 = 2;
+
+/// Newton 迭代次数上限, 未收敛即报错
+const MAX_ITERATIONS: usize = 255;
+
+/// 按两种 token 中较大的精度放大, 返回 (scale_a, scale_b)
+fn decimal_scales(decimals_a: u8, decimals_b: u8) -> Result<(u128, u128)> {
+    let target = decimals_a.max(decimals_b);
+    let scale = |decimals: u8| {
+        10u128
+            .checked_pow((target - decimals) as u32)
+            .ok_or(FallError::ArithmeticOverflow)
+    };
+    Ok((scale(decimals_a)?, scale(decimals_b)?))
+}
+
+/// a * b / c, 返回 (商, 余数). 乘积溢出 u128 时按 256 位计算
+fn mul_div_rem(a: u128, b: u128, c: u128) -> Result<(u128, u128)> {
+    require!(c > 0, FallError::CalculationError);
+    if let Some(product) = a.checked_mul(b) {
+        return Ok((product / c, product % c));
+    }
+
+    // 256 位乘积 (hi, lo)
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let mid = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+    let lo = (p00 & MASK) | (mid << 64);
+    let hi = a1 * b1 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+    require!(hi < c, FallError::ArithmeticOverflow);
+
+    // 逐位长除法
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((lo >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    Ok((quotient, remainder))
+}
+
+/// a * b / c, rounded down
+fn mul_div(a: u128, b: u128, c: u128) -> Result<u128> {
+    Ok(mul_div_rem(a, b, c)?.0)
+}
+
+/// D^(n+1) / (n^n·Πx), rounded up. 分步计算且不丢失精度:
+/// D² = q·2L + r, 则 D³/(4LS) = (q·D + r·D/2L) / 2S
+fn d_product_ceil(d: u128, x: u128, y: u128) -> Result<u128> {
+    let (small, large) = if x <= y { (x, y) } else { (y, x) };
+    let (q, r) = mul_div_rem(d, d, large * N_COINS)?;
+    let (q2, r2) = mul_div_rem(r, d, large * N_COINS)?;
+    let (a, ra) = mul_div_rem(q, d, small * N_COINS)?;
+    // 剩余部分 (ra + q2 + r2/2L) / 2S, 其中 ra < 2S, q2 <= D
+    let rest = ra.checked_add(q2).ok_or(FallError::ArithmeticOverflow)?;
+    let m = small * N_COINS;
+    let rest = if r2 > 0 { rest / m + 1 } else { rest.div_ceil(m) };
+    a.checked_add(rest).ok_or(error!(FallError::ArithmeticOverflow))
+}
+
+/// 余额 (x, y) 能否支撑不变量 D: A·n^n·Σx + D >= A·D·n^n + D^(n+1) / (n^n·Πx).
+/// 左减右随 D 递减、随 x, y 递增
+fn invariant_holds(amp: u64, x: u128, y: u128, d: u128) -> Result<bool> {
+    let ann = amp as u128 * N_COINS;
+    let lhs = x
+        .checked_add(y)
+        .and_then(|sum| ann.checked_mul(sum))
+        .and_then(|v| v.checked_add(d))
+        .ok_or(FallError::ArithmeticOverflow)?;
+    let rhs = ann
+        .checked_mul(d)
+        .and_then(|v| v.checked_add(d_product_ceil(d, x, y).ok()?))
+        .ok_or(FallError::ArithmeticOverflow)?;
+    Ok(lhs >= rhs)
+}
+
+/// StableSwap 不变量 D: A·n^n·Σx + D = A·D·n^n + D^(n+1) / (n^n·Πx), rounded down
+fn compute_d(amp: u64, x: u128, y: u128) -> Result<u128> {
+    require!(x > 0 && y > 0, FallError::InsufficientLiquidity);
+    let sum = x.checked_add(y).ok_or(FallError::ArithmeticOverflow)?;
+    let ann = amp as u128 * N_COINS;
+    // 从 D = Σx 开始自上而下收敛
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        let d_p = mul_div(d, d, x * N_COINS)?;
+        let d_p = mul_div(d_p, d, y * N_COINS)?;
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|v| v.checked_add(d_p.checked_mul(N_COINS)?))
+            .ok_or(FallError::ArithmeticOverflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|v| v.checked_add((N_COINS + 1).checked_mul(d_p)?))
+            .ok_or(FallError::ArithmeticOverflow)?;
+        let d_prev = d;
+        d = mul_div(numerator, d, denominator)?;
+        // 不再下降即视为收敛, 取较小者
+        if d.saturating_add(1) >= d_prev {
+            return round_d_down(amp, x, y, d.min(d_prev));
+        }
+    }
+    err!(FallError::CalculationError)
+}
+
+/// Newton 结果可能略高于真实 D, 逐步下调直到余额能支撑该 D
+fn round_d_down(amp: u64, x: u128, y: u128, mut d: u128) -> Result<u128> {
+    for _ in 0..MAX_ITERATIONS {
+        if invariant_holds(amp, x, y, d)? {
+            return Ok(d);
+        }
+        d = d.checked_sub(1).ok_or(FallError::CalculationError)?;
+    }
+    err!(FallError::CalculationError)
+}
+
+/// 给定一侧余额 x 与不变量 D, 求另一侧余额 y, rounded up
+fn compute_y(amp: u64, x: u128, d: u128) -> Result<u128> {
+    require!(x > 0, FallError::InsufficientLiquidity);
+    let ann = amp as u128 * N_COINS;
+    let c = mul_div(d, d, x * N_COINS)?;
+    let c = mul_div(c, d, ann * N_COINS)?;
+    let b = x.checked_add(d / ann).ok_or(FallError::ArithmeticOverflow)?;
+    // 从 y = D 开始, 第一步后位于根之上, 此后自上而下收敛
+    let mut y = d;
+    for i in 0..MAX_ITERATIONS {
+        let denominator = (2 * y)
+            .checked_add(b)
+            .and_then(|v| v.checked_sub(d))
+            .filter(|v| *v > 0)
+            .ok_or(FallError::CalculationError)?;
+        // (y² + c) / denominator, y² 可能溢出 u128
+        let (q, r) = mul_div_rem(y, y, denominator)?;
+        let y_prev = y;
+        y = (r + c % denominator) / denominator + q + c / denominator;
+        // 不再下降即视为收敛, 取较大者
+        if i > 0 && y.saturating_add(1) >= y_prev {
+            return round_y_up(amp, x, y.max(y_prev), d);
+        }
+    }
+    err!(FallError::CalculationError)
+}
+
+/// 逐步上调 y 直到 (x, y) 能支撑 D, 换出数量因此偏小
+fn round_y_up(amp: u64, x: u128, mut y: u128, d: u128) -> Result<u128> {
+    for _ in 0..MAX_ITERATIONS {
+        if invariant_holds(amp, x, y, d)? {
+            return Ok(y);
+        }
+        y = y.checked_add(1).ok_or(FallError::ArithmeticOverflow)?;
+    }
+    err!(FallError::CalculationError)
+}
+
+/// StableSwap: 扣除手续费后的输入 dx 换出的数量. 余额按精度对齐后计算,
+/// D 向下取整、新余额向上取整, 结果再向下取整, 舍入误差始终留在池中
+pub fn stable_swap(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    decimals_in: u8,
+    decimals_out: u8,
+    amp: u64,
+) -> Result<u64> {
+    require!(reserve_in > 0 && reserve_out > 0, FallError::InsufficientLiquidity);
+    let (scale_in, scale_out) = decimal_scales(decimals_in, decimals_out)?;
+    let x = reserve_in as u128 * scale_in;
+    let y = reserve_out as u128 * scale_out;
+    let d = compute_d(amp, x, y)?;
+
+    let new_x = x
+        .checked_add(amount_in as u128 * scale_in)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    let new_y = compute_y(amp, new_x, d)?;
+    let amount_out = y.saturating_sub(new_y) / scale_out;
+    u64::try_from(amount_out).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// StableSwap 不变量 D 不能因交易而减少: 新余额必须能支撑交易前的 D
+pub fn check_stable_swap(
+    amp: u64,
+    old_reserve_in: u64,
+    old_reserve_out: u64,
+    new_reserve_in: u64,
+    new_reserve_out: u64,
+    decimals_in: u8,
+    decimals_out: u8,
+) -> Result<()> {
+    let (scale_in, scale_out) = decimal_scales(decimals_in, decimals_out)?;
+    let old_d = compute_d(
+        amp,
+        old_reserve_in as u128 * scale_in,
+        old_reserve_out as u128 * scale_out,
+    )?;
+    let new_x = new_reserve_in as u128 * scale_in;
+    let new_y = new_reserve_out as u128 * scale_out;
+    require!(new_x > 0 && new_y > 0, FallError::InsufficientLiquidity);
+    require!(
+        invariant_holds(amp, new_x, new_y, old_d)?,
+        FallError::InvariantViolated
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_div_uses_256_bit_product() {
+        assert_eq!(mul_div(7, 3, 2).unwrap(), 10);
+        assert_eq!(mul_div_rem(7, 3, 2).unwrap(), (10, 1));
+
+        // u128::MAX * u128::MAX / u128::MAX
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX).unwrap(), u128::MAX);
+        // 2^127 * 6 / 4 = 3 * 2^126
+        assert_eq!(mul_div(1 << 127, 6, 4).unwrap(), 3 << 126);
+        // (2^100 + 1) * 2^60 / 2^50 = 2^110 + 2^10, 余数为 0
+        let a = (1u128 << 100) + 1;
+        assert_eq!(mul_div_rem(a, 1 << 60, 1 << 50).unwrap(), ((1 << 110) + (1 << 10), 0));
+        // (2^127 + 1) * 3 / 2^120 = 384, 余数 3
+        let a = (1u128 << 127) + 1;
+        assert_eq!(mul_div_rem(a, 3, 1 << 120).unwrap(), (384, 3));
+        // 商溢出 u128
+        assert!(mul_div(u128::MAX, u128::MAX, 2).is_err());
+        assert!(mul_div(1, 1, 0).is_err());
+    }
+
+    #[test]
+    fn compute_d_is_largest_supported_invariant() {
+        // 平衡池 D = Σx
+        assert_eq!(compute_d(100, 1_000_000, 1_000_000).unwrap(), 2_000_000);
+
+        for (amp, x, y) in [
+            (1, 1_000_000u128, 3_000_000u128),
+            (4, 3_112_464_738_929, 714_002_037_104),
+            (100, 10u128.pow(24), 10u128.pow(18)),
+            (1_000_000, 10u128.pow(27), 3 * 10u128.pow(26)),
+        ] {
+            let d = compute_d(amp, x, y).unwrap();
+            assert!(d <= x + y);
+            assert!(invariant_holds(amp, x, y, d).unwrap());
+            // 向下取整到能被支撑的最大值
+            assert!(!invariant_holds(amp, x, y, d + 1).unwrap());
+        }
+    }
+
+    #[test]
+    fn compute_y_is_smallest_supported_balance() {
+        for (amp, x, y) in [
+            (1, 1_000_000u128, 3_000_000u128),
+            (4, 3_112_464_738_929, 714_002_037_104),
+            (1_000_000, 10u128.pow(22), 3 * 10u128.pow(21)),
+        ] {
+            let d = compute_d(amp, x, y).unwrap();
+            let new_x = x + x / 3;
+            let new_y = compute_y(amp, new_x, d).unwrap();
+            assert!(new_y < y);
+            assert!(invariant_holds(amp, new_x, new_y, d).unwrap());
+            assert!(!invariant_holds(amp, new_x, new_y - 1, d).unwrap());
+        }
+    }
+
+    #[test]
+    fn stable_swap_quotes_pass_invariant_check() {
+        let (reserve_in, reserve_out, amp) = (3_112_464_738_929, 714_002_037_104, 4);
+        let amount_in = 931_751_878_419;
+        let out = stable_swap(amount_in, reserve_in, reserve_out, 6, 6, amp).unwrap();
+        assert!(out > 0 && out < reserve_out);
+        check_stable_swap(amp, reserve_in, reserve_out, reserve_in + amount_in, reserve_out - out, 6, 6)
+            .unwrap();
+        // 多换出 1 个单位即违反不变量
+        assert!(check_stable_swap(
+            amp,
+            reserve_in,
+            reserve_out,
+            reserve_in + amount_in,
+            reserve_out - out - 1,
+            6,
+            6
+        )
+        .is_err());
+
+        // 不同精度: 5000 个 9 位精度 token 换 4000 个 6 位精度 token
+        let (reserve_in, reserve_out) = (5_000_000_000_000, 4_000_000_000);
+        let out = stable_swap(1_000_000_000, reserve_in, reserve_out, 9, 6, 200).unwrap();
+        assert!(out > 990_000 && out < 1_000_000);
+        check_stable_swap(200, reserve_in, reserve_out, reserve_in + 1_000_000_000, reserve_out - out, 9, 6)
+        .unwrap();
+    }
+
+    #[test]
+    fn stable_swap_never_breaks_invariant() {
+        // 简单 LCG 生成的参数组合
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: u64| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 33) % bound + 1
+        };
+        for _ in 0..500 {
+            let amp = next(10_000);
+            let reserve_in = next(1 << 42) * 1_000;
+            let reserve_out = next(1 << 42) * 1_000;
+            let amount_in = next(reserve_in);
+            let out = stable_swap(amount_in, reserve_in, reserve_out, 6, 6, amp).unwrap();
+            if out == 0 || out >= reserve_out {
+                continue;
+            }
+            check_stable_swap(amp, reserve_in, reserve_out, reserve_in + amount_in, reserve_out - out, 6, 6)
+                .unwrap();
+        }
+    }
+}
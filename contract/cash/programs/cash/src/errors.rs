@@ -69,4 +69,94 @@ pub enum FallError {
 
     #[msg("Error13")]
     Error13,
+
+    #[msg("Insufficient pool liquidity")]
+    InsufficientLiquidity,
+
+    #[msg("Invalid collateral ratio")]
+    InvalidCollateralRatio,
+
+    #[msg("Position is below the minimum collateral ratio")]
+    PositionUnhealthy,
+
+    #[msg("Invalid interest rate model")]
+    InvalidRateModel,
+
+    #[msg("Invalid liquidation parameters")]
+    InvalidLiquidationParams,
+
+    #[msg("Position is not liquidatable")]
+    PositionNotLiquidatable,
+
+    #[msg("Invalid oracle account")]
+    InvalidOracleAccount,
+
+    #[msg("Invalid oracle price")]
+    InvalidOraclePrice,
+
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+
+    #[msg("Oracle confidence interval is too wide")]
+    OracleConfidenceTooWide,
+
+    #[msg("No pending admin for this signer")]
+    InvalidPendingAdmin,
+
+    #[msg("Operation is paused")]
+    Paused,
+
+    #[msg("Signer is not allowed to change pause flags")]
+    InvalidPauseAuthority,
+
+    #[msg("Position size exceeds the pool limit")]
+    PositionSizeExceeded,
+
+    #[msg("Pool deposit cap exceeded")]
+    DepositCapExceeded,
+
+    #[msg("Mint uses an unsupported token extension")]
+    UnsupportedMintExtension,
+
+    #[msg("Non-transferable receipts require the Token-2022 program")]
+    NonTransferableRequiresToken2022,
+
+    #[msg("Receipt owner does not match the pool's receipt mode")]
+    InvalidReceiptOwner,
+
+    #[msg("Pool kind does not match this instruction")]
+    InvalidPoolKind,
+
+    #[msg("Pool debt ceiling exceeded")]
+    DebtCeilingExceeded,
+
+    #[msg("A flash loan is in progress for this pool")]
+    FlashLoanActive,
+
+    #[msg("No flash loan in progress for this pool")]
+    NoActiveFlashLoan,
+
+    #[msg("Flash loans must be called directly, followed by a repay in the same transaction")]
+    FlashRepayMissing,
+
+    #[msg("Flash loan was not repaid with its fee")]
+    FlashLoanNotRepaid,
+
+    #[msg("Flash loan fee is out of range")]
+    InvalidFlashLoanFee,
+
+    #[msg("Flash mint amount exceeds the pool cap")]
+    FlashMintCapExceeded,
+
+    #[msg("Curve type does not match this instruction")]
+    InvalidCurveType,
+
+    #[msg("Amplification coefficient is out of range")]
+    InvalidAmp,
+
+    #[msg("Amplification ramp is too fast or too short")]
+    InvalidAmpRamp,
+
+    #[msg("Oracle and pool vault accounts are required to accrue interest")]
+    MissingAccrualAccounts,
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct FeeCharged {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    /// Total fee taken
+    pub fee: u64,
+    /// Part of the fee routed to the protocol fee vault
+    pub protocol_fee: u64,
+}
+
+#[event]
+pub struct BadDebtWrittenOff {
+    pub pool: Pubkey,
+    pub lender: Pubkey,
+    /// Outstanding debt (principal and interest) removed from the position
+    pub debt: u64,
+}
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use crate::{
+    errors::FallError,
+    state::Amm,
+};
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        constraint = new_admin.key() == amm.pending_admin @ FallError::InvalidPendingAdmin
+    )]
+    pub new_admin: Signer<'info>,
+}
+
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let amm = &mut ctx.accounts.amm;
+    amm.admin = ctx.accounts.new_admin.key();
+    amm.pending_admin = Pubkey::default();
+
+    Ok(())
+}
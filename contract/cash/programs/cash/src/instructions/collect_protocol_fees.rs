@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::{
+    constants::PROTOCOL_FEE_SEED,
+    state::Amm,
+};
+use crate::instructions::utils::transfer_tokens;
+
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub destination: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+
+pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+    let amm_key = ctx.accounts.amm.key();
+    let authority_seeds = &[
+        amm_key.as_ref(),
+        PROTOCOL_FEE_SEED,
+        &[ctx.bumps.protocol_fee_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    transfer_tokens(
+        &ctx.accounts.token_program,
+        &ctx.accounts.protocol_fee_vault,
+        &ctx.accounts.destination,
+        &ctx.accounts.mint,
+        &ctx.accounts.protocol_fee_authority.to_account_info(),
+        signer_seeds,
+        ctx.accounts.protocol_fee_vault.amount,
+    )?;
+
+    Ok(())
+}
@@ -1,11 +1,11 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenInterface},
 };
 use crate::{
-    constants::{AUTHORITY_SEED, CASH_POOL,CASH_TOKEN_SEED},
-    state::{Amm, Pool},
+    constants::{AUTHORITY_SEED, CASH_POOL, CASH_TOKEN_SEED, SCASH_TOKEN_SEED, CASH_DECIMALS},
+    state::{Amm, CashPool, Pool, PoolState},
 };
 
 #[derive(Accounts)]
@@ -18,56 +18,67 @@ pub struct CreateCashPool<'info> {
     )]
     pub amm: Box<Account<'info, Amm>>,
 
-    pub mint_a: Box<Account<'info, Mint>>,
+    /// CHECK: Read only authority, mints cash for every collateral pool of the AMM
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub amm_authority: AccountInfo<'info>,
 
     #[account(
-        mut,
+        init,
+        payer = payer,
         seeds = [
             amm.key().as_ref(),
-            mint_a.key().as_ref(),
             CASH_TOKEN_SEED,
         ],
         bump,
-        mint::decimals = 6,
-        mint::authority = pool_authority,
-        mint::freeze_authority = pool_authority,
+        mint::decimals = CASH_DECIMALS,
+        mint::authority = amm_authority,
+        mint::freeze_authority = amm_authority,
+        mint::token_program = token_program,
     )]
-    pub cash_token_mint: Box<Account<'info, Mint>>,
-    
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
     #[account(
         init,
         payer = payer,
-        space = Pool::LEN,
         seeds = [
             amm.key().as_ref(),
-            mint_a.key().as_ref(),
-            CASH_POOL,
+            SCASH_TOKEN_SEED,
         ],
         bump,
+        mint::decimals = CASH_DECIMALS,
+        mint::authority = amm_authority,
+        mint::freeze_authority = amm_authority,
+        mint::token_program = token_program,
     )]
-    pub cash_pool: Box<Account<'info, Pool>>,
-
-    /// CHECK: Read only authority
+    pub s_cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
+    
     #[account(
+        init,
+        payer = payer,
+        space = Pool::LEN,
         seeds = [
             amm.key().as_ref(),
-            mint_a.key().as_ref(),
-            AUTHORITY_SEED,
+            CASH_POOL,
         ],
         bump,
     )]
-    pub pool_authority: AccountInfo<'info>,
+    pub cash_pool: Box<Account<'info, Pool>>,
 
-    /// CHECK: Admin account from AMM state
     #[account(
         constraint = admin.key() == amm.admin
     )]
-    pub admin: AccountInfo<'info>,
+    pub admin: Signer<'info>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
@@ -76,14 +87,10 @@ pub struct CreateCashPool<'info> {
 pub fn create_cash_pool(ctx: Context<CreateCashPool>) -> Result<()> {
     let cash_pool = &mut ctx.accounts.cash_pool;
     cash_pool.amm = ctx.accounts.amm.key();
-    cash_pool.mint_a = ctx.accounts.mint_a.key();
-    cash_pool.pool_type = 1;
+    // cash 池以共享的 cash mint 作为自己的 token
+    cash_pool.mint_a = ctx.accounts.cash_token_mint.key();
+    cash_pool.mint_a_decimals = CASH_DECIMALS;
+    cash_pool.state = PoolState::Cash(CashPool::default());
 
     Ok(())
 }
-
-#[error_code]
-pub enum PoolError {
-    #[msg("Invalid fee")]
-    InvalidFee,
-}
\ No newline at end of file
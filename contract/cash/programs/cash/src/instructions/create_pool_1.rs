@@ -1,12 +1,19 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_2022::spl_token_2022::{extension::ExtensionType, state::Mint as MintState},
+    token_interface::{
+        self, InitializeMint2, Mint, NonTransferableMintInitialize, PermanentDelegateInitialize,
+        TokenAccount, TokenInterface,
+    },
 };
 use crate::{
-    constants::{AUTHORITY_SEED, LENDING_TOKEN_SEED,CASH_TOKEN_SEED,SCASH_TOKEN_SEED},
-    state::{Amm, Pool},
+    constants::{AUTHORITY_SEED, LENDING_TOKEN_SEED,INDEX_PRECISION,DEFAULT_KINK,DEFAULT_LIQUIDATION_BONUS,DEFAULT_CLOSE_FACTOR,DEFAULT_FLASH_LOAN_FEE},
+    errors::FallError,
+    state::{Amm, CollateralPool, Pool, PoolState},
 };
+use crate::instructions::utils::{validate_collateral_mint, validate_collateral_ratio};
 
 #[derive(Accounts)]
 pub struct CreatePool1<'info> {
@@ -18,7 +25,10 @@ pub struct CreatePool1<'info> {
     )]
     pub amm: Box<Account<'info, Amm>>,
 
-    pub mint_a: Box<Account<'info, Mint>>,
+    #[account(
+        mint::token_program = collateral_token_program,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
     
     #[account(
         init,
@@ -48,80 +58,305 @@ pub struct CreatePool1<'info> {
         payer = payer,
         associated_token::mint = mint_a,
         associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub pool_account_a: Box<Account<'info, TokenAccount>>,
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
     
+    /// CHECK: Created in the handler, the NonTransferable extension is optional per pool
     #[account(
-        init,
-        payer = payer,
+        mut,
         seeds = [
             amm.key().as_ref(),
             mint_a.key().as_ref(),
             LENDING_TOKEN_SEED,
         ],
         bump,
-        mint::decimals = 6,
-        mint::authority = pool_authority,
-        mint::freeze_authority = pool_authority,
     )]
-    pub lending_receipt_token_mint: Box<Account<'info, Mint>>,
+    pub lending_receipt_token_mint: AccountInfo<'info>,
         
-    #[account(
-        init,
-        payer = payer,
-        seeds = [
-            amm.key().as_ref(),
-            mint_a.key().as_ref(),
-            CASH_TOKEN_SEED,
-        ],
-        bump,
-        mint::decimals = 6,
-        mint::authority = pool_authority,
-        mint::freeze_authority = pool_authority,
-    )]
-    pub cash_token_mint: Box<Account<'info, Mint>>,
-
-    #[account(
-        init,
-        payer = payer,
-        seeds = [
-            amm.key().as_ref(),
-            mint_a.key().as_ref(),
-            SCASH_TOKEN_SEED,
-        ],
-        bump,
-        mint::decimals = 6,
-        mint::authority = pool_authority,
-        mint::freeze_authority = pool_authority,
-    )]
-    pub s_cash_token_mint: Box<Account<'info, Mint>>,
-
-    /// CHECK: Admin account from AMM state
     #[account(
         constraint = admin.key() == amm.admin
     )]
-    pub admin: AccountInfo<'info>,
+    pub admin: Signer<'info>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub receipt_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 
-pub fn create_pool_1(ctx: Context<CreatePool1>) -> Result<()> {
+pub fn create_pool_1(
+    ctx: Context<CreatePool1>,
+    collateral_ratio: u64,
+    min_collateral_ratio: u64,
+    non_transferable_receipts: bool,
+    debt_ceiling: u64,
+) -> Result<()> {
+    validate_collateral_ratio(collateral_ratio, min_collateral_ratio)?;
+    validate_collateral_mint(&ctx.accounts.mint_a)?;
+    create_receipt_mint(&ctx, non_transferable_receipts)?;
+
     let pool = &mut ctx.accounts.pool;
     pool.amm = ctx.accounts.amm.key();
     pool.mint_a = ctx.accounts.mint_a.key();
-    pool.pool_type = 0;
+    pool.mint_a_decimals = ctx.accounts.mint_a.decimals;
+    pool.state = PoolState::Collateral(CollateralPool {
+        collateral_ratio,
+        min_collateral_ratio,
+        debt_ceiling,
+        borrow_index: INDEX_PRECISION,
+        last_accrual_timestamp: Clock::get()?.unix_timestamp,
+        kink: DEFAULT_KINK,
+        liquidation_bonus: DEFAULT_LIQUIDATION_BONUS,
+        close_factor: DEFAULT_CLOSE_FACTOR,
+        flash_loan_fee: DEFAULT_FLASH_LOAN_FEE,
+        flash_mint_fee: DEFAULT_FLASH_LOAN_FEE,
+        non_transferable_receipts,
+        ..Default::default()
+    });
 
     Ok(())
 }
 
-#[error_code]
-pub enum PoolError {
-    #[msg("Invalid fee")]
-    InvalidFee,
-}
\ No newline at end of file
+/// 创建 lending receipt mint. NonTransferable 模式下 receipt 留在 lender 钱包,
+/// pool_authority 作为 permanent delegate 在赎回、清算时销毁 receipt
+fn create_receipt_mint(ctx: &Context<CreatePool1>, non_transferable: bool) -> Result<()> {
+    let extensions: &[ExtensionType] = if non_transferable {
+        require_keys_eq!(
+            ctx.accounts.receipt_token_program.key(),
+            token_interface::spl_token_2022::ID,
+            FallError::NonTransferableRequiresToken2022
+        );
+        &[ExtensionType::NonTransferable, ExtensionType::PermanentDelegate]
+    } else {
+        &[]
+    };
+
+    let space = ExtensionType::try_calculate_account_len::<MintState>(extensions)?;
+    let amm_key = ctx.accounts.amm.key();
+    let mint_a_key = ctx.accounts.mint_a.key();
+    let mint_seeds = &[
+        amm_key.as_ref(),
+        mint_a_key.as_ref(),
+        LENDING_TOKEN_SEED,
+        &[ctx.bumps.lending_receipt_token_mint],
+    ];
+    create_pda_account(
+        &ctx.accounts.system_program,
+        &ctx.accounts.payer,
+        &ctx.accounts.lending_receipt_token_mint,
+        &[&mint_seeds[..]],
+        space,
+        &ctx.accounts.receipt_token_program.key(),
+    )?;
+
+    let token_program = ctx.accounts.receipt_token_program.to_account_info();
+    let mint = ctx.accounts.lending_receipt_token_mint.to_account_info();
+    if non_transferable {
+        token_interface::non_transferable_mint_initialize(CpiContext::new(
+            token_program.clone(),
+            NonTransferableMintInitialize {
+                token_program_id: token_program.clone(),
+                mint: mint.clone(),
+            },
+        ))?;
+        token_interface::permanent_delegate_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                PermanentDelegateInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            &ctx.accounts.pool_authority.key(),
+        )?;
+    }
+
+    token_interface::initialize_mint2(
+        CpiContext::new(token_program, InitializeMint2 { mint }),
+        6,
+        &ctx.accounts.pool_authority.key(),
+        Some(&ctx.accounts.pool_authority.key()),
+    )
+}
+
+/// 与 Anchor `init` 相同地创建 PDA 账户: 地址可被任何人预先转入 lamports,
+/// 此时不能 create_account, 改为补足租金后 allocate + assign
+fn create_pda_account<'info>(
+    system_program: &Program<'info, System>,
+    payer: &Signer<'info>,
+    account: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    space: usize,
+    owner: &Pubkey,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    let lamports = account.lamports();
+    if lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+
+    if lamports < rent {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            rent - lamports,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            signer_seeds,
+        ),
+        owner,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{
+        solana_program::entrypoint::ProgramResult, system_program, InstructionData, ToAccountMetas,
+    };
+    use anchor_spl::{associated_token, token::spl_token, token_2022::spl_token_2022};
+    use crate::test_utils::Bank;
+    use super::*;
+
+    struct Accounts {
+        amm: Pubkey,
+        mint_a: Pubkey,
+        pool: Pubkey,
+        pool_authority: Pubkey,
+        pool_account_a: Pubkey,
+        lending_receipt_token_mint: Pubkey,
+        admin: Pubkey,
+    }
+
+    fn pda(seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &crate::ID).0
+    }
+
+    fn setup(bank: &mut Bank) -> Accounts {
+        let id = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let amm = pda(&[id.as_ref()]);
+        let mint_a = Pubkey::new_unique();
+        let pool_authority = pda(&[amm.as_ref(), mint_a.as_ref(), AUTHORITY_SEED]);
+
+        bank.set_account(amm, &Amm { id, admin, ..Default::default() }, Amm::LEN);
+        bank.set_mint(mint_a, spl_token::ID, Pubkey::new_unique(), 0, 9);
+        bank.set_wallet(admin);
+        Accounts {
+            amm,
+            mint_a,
+            pool: pda(&[amm.as_ref(), mint_a.as_ref()]),
+            pool_authority,
+            pool_account_a: associated_token::get_associated_token_address_with_program_id(
+                &pool_authority,
+                &mint_a,
+                &spl_token::ID,
+            ),
+            lending_receipt_token_mint: pda(&[amm.as_ref(), mint_a.as_ref(), LENDING_TOKEN_SEED]),
+            admin,
+        }
+    }
+
+    fn create(
+        bank: &mut Bank,
+        accounts: &Accounts,
+        receipt_token_program: Pubkey,
+        non_transferable_receipts: bool,
+    ) -> ProgramResult {
+        let metas = crate::accounts::CreatePool1 {
+            amm: accounts.amm,
+            mint_a: accounts.mint_a,
+            pool: accounts.pool,
+            pool_authority: accounts.pool_authority,
+            pool_account_a: accounts.pool_account_a,
+            lending_receipt_token_mint: accounts.lending_receipt_token_mint,
+            admin: accounts.admin,
+            payer: accounts.admin,
+            receipt_token_program,
+            collateral_token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        let data = crate::instruction::CreatePool1 {
+            collateral_ratio: 15_000,
+            min_collateral_ratio: 12_000,
+            non_transferable_receipts,
+            debt_ceiling: 1_000_000,
+        }
+        .data();
+        bank.process(metas, data)
+    }
+
+    #[test]
+    fn create_pool_1_initializes_receipt_mint() {
+        let mut bank = Bank::new();
+        let accounts = setup(&mut bank);
+        create(&mut bank, &accounts, spl_token_2022::ID, true).unwrap();
+
+        assert_eq!(bank.owner(&accounts.lending_receipt_token_mint), spl_token_2022::ID);
+        assert_eq!(bank.mint_supply(&accounts.lending_receipt_token_mint), 0);
+        let pool: Pool = bank.account(&accounts.pool);
+        let state = pool.collateral().unwrap();
+        assert_eq!(state.debt_ceiling, 1_000_000);
+        assert!(state.non_transferable_receipts);
+    }
+
+    #[test]
+    fn create_pool_1_accepts_prefunded_receipt_mint() {
+        let mut bank = Bank::new();
+        let accounts = setup(&mut bank);
+        // 任何人都可以先向 receipt mint 地址转入 lamports
+        bank.set_raw(accounts.lending_receipt_token_mint, system_program::ID, vec![]);
+        create(&mut bank, &accounts, spl_token::ID, false).unwrap();
+
+        assert_eq!(bank.owner(&accounts.lending_receipt_token_mint), spl_token::ID);
+        assert!(bank.lamports(&accounts.lending_receipt_token_mint) >= 1_000_000_000);
+        assert_eq!(bank.mint_supply(&accounts.lending_receipt_token_mint), 0);
+    }
+
+    #[test]
+    fn create_pool_1_rejects_non_transferable_on_classic_spl() {
+        let mut bank = Bank::new();
+        let accounts = setup(&mut bank);
+        let err = create(&mut bank, &accounts, spl_token::ID, true).unwrap_err();
+        assert_eq!(err, Error::from(FallError::NonTransferableRequiresToken2022).into());
+    }
+}
@@ -0,0 +1,107 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, CASH_TOKEN_SEED},
+    errors::FallError,
+    state::{Amm, Pool, PoolState, PsmPool},
+};
+use crate::instructions::utils::{validate_collateral_mint, validate_psm_fee};
+
+#[derive(Accounts)]
+pub struct CreatePsmPool<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    /// 白名单稳定币, 不能是 cash 本身
+    #[account(
+        mint::token_program = collateral_token_program,
+        constraint = mint_a.key() != cash_token_mint.key() @ FallError::InvalidMint,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            CASH_TOKEN_SEED,
+        ],
+        bump,
+    )]
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Pool::LEN,
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// PSM 金库
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+
+pub fn create_psm_pool(
+    ctx: Context<CreatePsmPool>,
+    debt_ceiling: u64,
+    psm_mint_fee: u64,
+    psm_redeem_fee: u64,
+) -> Result<()> {
+    validate_collateral_mint(&ctx.accounts.mint_a)?;
+    validate_psm_fee(psm_mint_fee)?;
+    validate_psm_fee(psm_redeem_fee)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.amm = ctx.accounts.amm.key();
+    pool.mint_a = ctx.accounts.mint_a.key();
+    pool.mint_a_decimals = ctx.accounts.mint_a.decimals;
+    pool.state = PoolState::Psm(PsmPool {
+        debt_ceiling,
+        psm_mint_fee,
+        psm_redeem_fee,
+    });
+
+    Ok(())
+}
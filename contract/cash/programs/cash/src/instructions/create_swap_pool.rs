@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, LP_TOKEN_SEED, SWAP_POOL_SEED},
+    errors::FallError,
+    state::{Amm, CurveType, SwapPool},
+};
+use crate::instructions::utils::{validate_amp, validate_collateral_mint};
+
+#[derive(Accounts)]
+pub struct CreateSwapPool<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mint::token_program = token_program_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program_b,
+        constraint = mint_a.key() < mint_b.key() @ FallError::InvalidMint,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = SwapPool::LEN,
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            SWAP_POOL_SEED,
+        ],
+        bump,
+    )]
+    pub swap_pool: Box<Account<'info, SwapPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            swap_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub swap_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            swap_pool.key().as_ref(),
+            LP_TOKEN_SEED,
+        ],
+        bump,
+        mint::decimals = 6,
+        mint::authority = swap_pool_authority,
+        mint::token_program = token_program,
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_a,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_b,
+    )]
+    pub pool_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// amp 仅对 StableSwap 有效, 恒定乘积池须传 0
+pub fn create_swap_pool(ctx: Context<CreateSwapPool>, curve_type: CurveType, amp: u64) -> Result<()> {
+    validate_collateral_mint(&ctx.accounts.mint_a)?;
+    validate_collateral_mint(&ctx.accounts.mint_b)?;
+    match curve_type {
+        CurveType::ConstantProduct => require!(amp == 0, FallError::InvalidAmp),
+        CurveType::StableSwap => validate_amp(amp)?,
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let swap_pool = &mut ctx.accounts.swap_pool;
+    swap_pool.amm = ctx.accounts.amm.key();
+    swap_pool.mint_a = ctx.accounts.mint_a.key();
+    swap_pool.mint_b = ctx.accounts.mint_b.key();
+    swap_pool.curve_type = curve_type;
+    swap_pool.initial_amp = amp;
+    swap_pool.target_amp = amp;
+    swap_pool.ramp_start_timestamp = now;
+    swap_pool.ramp_stop_timestamp = now;
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, LP_TOKEN_SEED, PAUSE_DEPOSITS, SWAP_POOL_SEED},
+    curve::{deposit_amounts, liquidity_for_deposit},
+    errors::FallError,
+    state::{Amm, SwapPool},
+};
+use crate::instructions::utils::{mint_token, require_amm_not_paused, transfer_tokens};
+
+#[derive(Accounts)]
+pub struct DepositLiquidity<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        seeds = [
+            swap_pool.amm.as_ref(),
+            swap_pool.mint_a.key().as_ref(),
+            swap_pool.mint_b.key().as_ref(),
+            SWAP_POOL_SEED,
+        ],
+        bump,
+        has_one = amm,
+    )]
+    pub swap_pool: Box<Account<'info, SwapPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            swap_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub swap_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        address = swap_pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        address = swap_pool.mint_b,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            swap_pool.key().as_ref(),
+            LP_TOKEN_SEED,
+        ],
+        bump,
+        mint::authority = swap_pool_authority,
+        mint::token_program = token_program,
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_a,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_b,
+    )]
+    pub pool_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::token_program = token_program_a,
+    )]
+    pub provider_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b,
+    )]
+    pub provider_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = lp_token_mint,
+        associated_token::authority = provider,
+        associated_token::token_program = token_program,
+    )]
+    pub provider_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn deposit_liquidity(
+    ctx: Context<DepositLiquidity>,
+    max_amount_a: u64,
+    max_amount_b: u64,
+    min_lp_amount: u64,
+) -> Result<()> {
+    require_amm_not_paused(&ctx.accounts.amm, PAUSE_DEPOSITS)?;
+    require!(max_amount_a > 0 && max_amount_b > 0, FallError::DepositTooSmall);
+
+    // 1. 按当前储备比例确定注入数量
+    let reserve_a = ctx.accounts.pool_account_a.amount;
+    let reserve_b = ctx.accounts.pool_account_b.amount;
+    let lp_supply = ctx.accounts.lp_token_mint.supply;
+    let (amount_a, amount_b) =
+        deposit_amounts(max_amount_a, max_amount_b, reserve_a, reserve_b, lp_supply)?;
+
+    // 2. 转入 token a / token b, 以池子实际收到的数量计算 LP
+    transfer_tokens(
+        &ctx.accounts.token_program_a,
+        &ctx.accounts.provider_token_a,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.provider.to_account_info(),
+        &[],
+        amount_a,
+    )?;
+    transfer_tokens(
+        &ctx.accounts.token_program_b,
+        &ctx.accounts.provider_token_b,
+        &ctx.accounts.pool_account_b,
+        &ctx.accounts.mint_b,
+        &ctx.accounts.provider.to_account_info(),
+        &[],
+        amount_b,
+    )?;
+    ctx.accounts.pool_account_a.reload()?;
+    ctx.accounts.pool_account_b.reload()?;
+    let received_a = ctx.accounts.pool_account_a.amount.saturating_sub(reserve_a);
+    let received_b = ctx.accounts.pool_account_b.amount.saturating_sub(reserve_b);
+
+    let lp_amount = liquidity_for_deposit(received_a, received_b, reserve_a, reserve_b, lp_supply)?;
+    require!(lp_amount > 0, FallError::DepositTooSmall);
+    require!(lp_amount >= min_lp_amount, FallError::OutputTooSmall);
+
+    // 3. 铸造 LP
+    let swap_pool_key = ctx.accounts.swap_pool.key();
+    let authority_seeds = &[
+        swap_pool_key.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.swap_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    mint_token(
+        &ctx.accounts.token_program,
+        &ctx.accounts.lp_token_mint,
+        &ctx.accounts.provider_lp_token,
+        &ctx.accounts.swap_pool_authority,
+        signer_seeds,
+        lp_amount,
+    )?;
+
+    Ok(())
+}
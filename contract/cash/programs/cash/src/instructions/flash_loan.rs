@@ -0,0 +1,467 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use anchor_lang::Discriminator;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, PAUSE_WITHDRAWALS, PROTOCOL_FEE_SEED, RATIO_PRECISION},
+    errors::FallError,
+    events::FeeCharged,
+    state::{Amm, Pool, PoolKind},
+};
+use crate::instructions::utils::{
+    mul_div, mul_div_ceil, reject_transfer_fee_mint, require_flash_repay, require_not_paused,
+    transfer_tokens,
+};
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        address = pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = pool.mint_a,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = pool.mint_a,
+        token::token_program = collateral_token_program,
+    )]
+    pub borrower_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Instructions sysvar, used to find the matching flash_repay
+    #[account(
+        address = sysvar_instructions::ID,
+    )]
+    pub instructions: AccountInfo<'info>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        address = pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = pool.mint_a,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub repayer: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = pool.mint_a,
+        token::token_program = collateral_token_program,
+    )]
+    pub repayer_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    /// 协议金库, 接收闪电贷手续费
+    #[account(
+        init_if_needed,
+        payer = repayer,
+        associated_token::mint = mint_a,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.pool, PAUSE_WITHDRAWALS)?;
+    require!(ctx.accounts.pool.collateral()?.flash_loan_amount == 0, FallError::FlashLoanActive);
+    require!(amount > 0, FallError::DepositTooSmall);
+    reject_transfer_fee_mint(&ctx.accounts.mint_a)?;
+    require!(
+        ctx.accounts.pool_account_a.amount >= amount,
+        FallError::InsufficientLiquidity
+    );
+
+    // 1. 同一交易中必须有针对该 pool 的 flash_repay
+    require_flash_repay(
+        &ctx.accounts.instructions,
+        crate::instruction::FlashRepay::DISCRIMINATOR,
+        ctx.accounts.pool.key(),
+    )?;
+
+    // 2. 借出 token a
+    let authority_seeds = &[
+        ctx.accounts.pool.amm.as_ref(),
+        ctx.accounts.pool.mint_a.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.borrower_token_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.pool_authority.to_account_info(),
+        signer_seeds,
+        amount,
+    )?;
+
+    ctx.accounts.pool.collateral_mut()?.flash_loan_amount = amount;
+
+    Ok(())
+}
+
+pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+    let amount = ctx.accounts.pool.collateral()?.flash_loan_amount;
+    require!(amount > 0, FallError::NoActiveFlashLoan);
+
+    // 1. 手续费按 protocol_fee_percentage 拆分: 协议部分进入协议金库, 其余留在池子
+    let fee = mul_div_ceil(amount, ctx.accounts.pool.collateral()?.flash_loan_fee, RATIO_PRECISION)?;
+    let protocol_fee = mul_div(
+        fee,
+        ctx.accounts.amm.protocol_fee_percentage as u64,
+        RATIO_PRECISION,
+    )?;
+    let pool_fee = fee - protocol_fee;
+    let repay_amount = amount
+        .checked_add(pool_fee)
+        .ok_or(FallError::ArithmeticOverflow)?;
+
+    // 2. 归还本金和池子手续费, 以池子实际收到的数量为准
+    let balance_before = ctx.accounts.pool_account_a.amount;
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.repayer_token_a,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.repayer.to_account_info(),
+        &[],
+        repay_amount,
+    )?;
+    ctx.accounts.pool_account_a.reload()?;
+    require!(
+        ctx.accounts.pool_account_a.amount.saturating_sub(balance_before) >= repay_amount,
+        FallError::FlashLoanNotRepaid
+    );
+
+    if protocol_fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.collateral_token_program,
+            &ctx.accounts.repayer_token_a,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.mint_a,
+            &ctx.accounts.repayer.to_account_info(),
+            &[],
+            protocol_fee,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.token_a_amount = pool
+        .token_a_amount
+        .checked_add(pool_fee)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.collateral_mut()?.flash_loan_amount = 0;
+    emit!(FeeCharged {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.repayer.key(),
+        fee,
+        protocol_fee,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{
+        solana_program::{entrypoint::ProgramResult, instruction::Instruction, program_pack::Pack},
+        system_program, InstructionData, ToAccountMetas,
+    };
+    use anchor_spl::{
+        associated_token::{self, get_associated_token_address_with_program_id},
+        token::spl_token,
+        token_2022::spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::TransferFeeConfig, BaseStateWithExtensionsMut, ExtensionType,
+                StateWithExtensionsMut,
+            },
+        },
+    };
+    use crate::{
+        state::{CollateralPool, PoolState},
+        test_utils::Bank,
+    };
+    use super::*;
+
+    struct FlashPool {
+        amm: Pubkey,
+        pool: Pubkey,
+        pool_authority: Pubkey,
+        mint_a: Pubkey,
+        pool_account_a: Pubkey,
+        borrower: Pubkey,
+        borrower_token_a: Pubkey,
+        protocol_fee_authority: Pubkey,
+        protocol_fee_vault: Pubkey,
+        token_program: Pubkey,
+    }
+
+    fn pda(seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &crate::ID).0
+    }
+
+    /// 抵押池持有 1_000_000 token a, 闪电贷手续费 0.09%, 协议分成 20%
+    fn setup(bank: &mut Bank, mint_a: Pubkey, token_program: Pubkey) -> FlashPool {
+        let id = Pubkey::new_unique();
+        let amm = pda(&[id.as_ref()]);
+        let pool = pda(&[amm.as_ref(), mint_a.as_ref()]);
+        let pool_authority = pda(&[amm.as_ref(), mint_a.as_ref(), AUTHORITY_SEED]);
+        let protocol_fee_authority = pda(&[amm.as_ref(), PROTOCOL_FEE_SEED]);
+        let borrower = Pubkey::new_unique();
+
+        bank.set_account(
+            amm,
+            &Amm {
+                id,
+                protocol_fee_percentage: 2000,
+                ..Default::default()
+            },
+            Amm::LEN,
+        );
+        bank.set_account(
+            pool,
+            &Pool {
+                amm,
+                mint_a,
+                token_a_amount: 1_000_000,
+                state: PoolState::Collateral(CollateralPool {
+                    flash_loan_fee: 9,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            Pool::LEN,
+        );
+        bank.set_wallet(borrower);
+        FlashPool {
+            amm,
+            pool,
+            pool_authority,
+            mint_a,
+            pool_account_a: bank.set_ata(pool_authority, mint_a, token_program, 1_000_000),
+            borrower,
+            borrower_token_a: bank.set_ata(borrower, mint_a, token_program, 10_000),
+            protocol_fee_authority,
+            protocol_fee_vault: get_associated_token_address_with_program_id(
+                &protocol_fee_authority,
+                &mint_a,
+                &token_program,
+            ),
+            token_program,
+        }
+    }
+
+    fn borrow_ix(pool: &FlashPool, amount: u64) -> Instruction {
+        Instruction {
+            program_id: crate::ID,
+            accounts: crate::accounts::FlashBorrow {
+                amm: pool.amm,
+                pool: pool.pool,
+                pool_authority: pool.pool_authority,
+                mint_a: pool.mint_a,
+                pool_account_a: pool.pool_account_a,
+                borrower: pool.borrower,
+                borrower_token_a: pool.borrower_token_a,
+                instructions: sysvar_instructions::ID,
+                collateral_token_program: pool.token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::FlashBorrow { amount }.data(),
+        }
+    }
+
+    fn repay_ix(pool: &FlashPool) -> Instruction {
+        Instruction {
+            program_id: crate::ID,
+            accounts: crate::accounts::FlashRepay {
+                amm: pool.amm,
+                pool: pool.pool,
+                pool_authority: pool.pool_authority,
+                mint_a: pool.mint_a,
+                pool_account_a: pool.pool_account_a,
+                repayer: pool.borrower,
+                repayer_token_a: pool.borrower_token_a,
+                protocol_fee_authority: pool.protocol_fee_authority,
+                protocol_fee_vault: pool.protocol_fee_vault,
+                collateral_token_program: pool.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::FlashRepay {}.data(),
+        }
+    }
+
+    fn process(bank: &mut Bank, ix: Instruction) -> ProgramResult {
+        bank.process(ix.accounts, ix.data)
+    }
+
+    #[test]
+    fn flash_repay_splits_fee_between_pool_and_protocol() {
+        let mut bank = Bank::new();
+        let mint_a = Pubkey::new_unique();
+        bank.set_mint(mint_a, spl_token::ID, Pubkey::new_unique(), 1_010_000, 6);
+        let pool = setup(&mut bank, mint_a, spl_token::ID);
+        let transaction = [borrow_ix(&pool, 1_000_000), repay_ix(&pool)];
+        bank.set_instructions(&transaction, 0);
+        process(&mut bank, transaction[0].clone()).unwrap();
+        assert_eq!(bank.token_amount(&pool.pool_account_a), 0);
+        bank.set_instructions(&transaction, 1);
+        process(&mut bank, transaction[1].clone()).unwrap();
+
+        // fee = ceil(1_000_000 * 0.09%) = 900, 协议 20% = 180, 池子 720
+        assert_eq!(bank.token_amount(&pool.pool_account_a), 1_000_720);
+        assert_eq!(bank.token_amount(&pool.protocol_fee_vault), 180);
+        assert_eq!(bank.token_amount(&pool.borrower_token_a), 10_000 - 900);
+        let state: Pool = bank.account(&pool.pool);
+        assert_eq!(state.token_a_amount, 1_000_720);
+        assert_eq!(state.collateral().unwrap().flash_loan_amount, 0);
+    }
+
+    #[test]
+    fn flash_borrow_requires_repay_in_same_transaction() {
+        let mut bank = Bank::new();
+        let mint_a = Pubkey::new_unique();
+        bank.set_mint(mint_a, spl_token::ID, Pubkey::new_unique(), 1_010_000, 6);
+        let pool = setup(&mut bank, mint_a, spl_token::ID);
+        let transaction = [borrow_ix(&pool, 1_000)];
+        bank.set_instructions(&transaction, 0);
+        let err = process(&mut bank, transaction[0].clone()).unwrap_err();
+        assert_eq!(err, Error::from(FallError::FlashRepayMissing).into());
+    }
+
+    #[test]
+    fn flash_borrow_rejects_transfer_fee_mint() {
+        let mut bank = Bank::new();
+        let mint_a = Pubkey::new_unique();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let mut data = vec![0; space];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+                .unwrap();
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+        config.newer_transfer_fee.maximum_fee = u64::MAX.into();
+        state.base = spl_token_2022::state::Mint {
+            supply: 1_010_000,
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        assert!(data.len() > spl_token_2022::state::Mint::LEN);
+        bank.set_raw(mint_a, spl_token_2022::ID, data);
+
+        let pool = setup(&mut bank, mint_a, spl_token_2022::ID);
+        let transaction = [borrow_ix(&pool, 1_000), repay_ix(&pool)];
+        bank.set_instructions(&transaction, 0);
+        let err = process(&mut bank, transaction[0].clone()).unwrap_err();
+        assert_eq!(err, Error::from(FallError::UnsupportedMintExtension).into());
+    }
+}
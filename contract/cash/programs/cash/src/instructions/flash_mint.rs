@@ -0,0 +1,226 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as sysvar_instructions;
+use anchor_lang::Discriminator;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, Burn, Mint, TokenAccount, TokenInterface},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, CASH_TOKEN_SEED, PAUSE_MINTING, RATIO_PRECISION},
+    errors::FallError,
+    events::FeeCharged,
+    state::{Amm, Pool, PoolKind},
+};
+use crate::instructions::utils::{
+    mint_token, mul_div_ceil, require_flash_repay, require_not_paused, transfer_tokens,
+};
+
+#[derive(Accounts)]
+pub struct FlashMint<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    /// 提供闪电铸造额度的抵押池
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority, mint authority of the shared cash mint
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub amm_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            amm.key().as_ref(),
+            CASH_TOKEN_SEED,
+        ],
+        bump,
+        mint::authority = amm_authority,
+        mint::token_program = token_program,
+    )]
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = cash_token_mint,
+        token::token_program = token_program,
+    )]
+    pub receiver_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Instructions sysvar, used to find the matching flash_mint_repay
+    #[account(
+        address = sysvar_instructions::ID,
+    )]
+    pub instructions: AccountInfo<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct FlashMintRepay<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub amm_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            amm.key().as_ref(),
+            CASH_TOKEN_SEED,
+        ],
+        bump,
+        mint::authority = amm_authority,
+        mint::token_program = token_program,
+    )]
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// cash 池金库, 接收手续费
+    #[account(
+        init_if_needed,
+        payer = repayer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = amm_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_account_cash: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub repayer: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = cash_token_mint,
+        token::token_program = token_program,
+    )]
+    pub repayer_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn flash_mint(ctx: Context<FlashMint>, amount: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.pool, PAUSE_MINTING)?;
+    require!(ctx.accounts.pool.collateral()?.flash_mint_amount == 0, FallError::FlashLoanActive);
+    require!(amount > 0, FallError::DepositTooSmall);
+    require!(
+        amount <= ctx.accounts.pool.collateral()?.flash_mint_cap,
+        FallError::FlashMintCapExceeded
+    );
+
+    // 1. 同一交易中必须有针对该 pool 的 flash_mint_repay
+    require_flash_repay(
+        &ctx.accounts.instructions,
+        crate::instruction::FlashMintRepay::DISCRIMINATOR,
+        ctx.accounts.pool.key(),
+    )?;
+
+    // 2. 铸造 cash
+    let amm_key = ctx.accounts.amm.key();
+    let authority_seeds = &[
+        amm_key.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.amm_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    mint_token(
+        &ctx.accounts.token_program,
+        &ctx.accounts.cash_token_mint,
+        &ctx.accounts.receiver_cash_token,
+        &ctx.accounts.amm_authority,
+        signer_seeds,
+        amount,
+    )?;
+
+    ctx.accounts.pool.collateral_mut()?.flash_mint_amount = amount;
+
+    Ok(())
+}
+
+pub fn flash_mint_repay(ctx: Context<FlashMintRepay>) -> Result<()> {
+    let amount = ctx.accounts.pool.collateral()?.flash_mint_amount;
+    require!(amount > 0, FallError::NoActiveFlashLoan);
+
+    // 1. 销毁闪电铸造的 cash
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.cash_token_mint.to_account_info(),
+                from: ctx.accounts.repayer_cash_token.to_account_info(),
+                authority: ctx.accounts.repayer.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    // 2. 手续费进入 cash 池
+    let fee = mul_div_ceil(amount, ctx.accounts.pool.collateral()?.flash_mint_fee, RATIO_PRECISION)?;
+    if fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.repayer_cash_token,
+            &ctx.accounts.pool_account_cash,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.repayer.to_account_info(),
+            &[],
+            fee,
+        )?;
+    }
+
+    ctx.accounts.pool.collateral_mut()?.flash_mint_amount = 0;
+    emit!(FeeCharged {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.repayer.key(),
+        fee,
+        protocol_fee: 0,
+    });
+
+    Ok(())
+}
@@ -1,24 +1,40 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{self, Mint, Token, TokenAccount, Transfer},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 use crate::{
-    constants::{LENDING_TOKEN_SEED,CASH_TOKEN_SEED,AUTHORITY_SEED},
-    state::Pool,
+    constants::{LENDING_TOKEN_SEED,CASH_TOKEN_SEED,AUTHORITY_SEED,LENDER_POSITION_SEED,RATIO_PRECISION,PROTOCOL_FEE_SEED,PAUSE_DEPOSITS,PAUSE_MINTING},
+    errors::FallError,
+    events::FeeCharged,
+    oracle::{collateral_value, get_price},
+    state::{Amm, LenderPosition, Pool, PoolKind},
+};
+use crate::instructions::utils::{
+    accrue_interest, check_debt_ceiling, check_deposit_limits, compute_fees, mint_and_freeze_token,
+    mint_token, mul_div, require_not_paused, scaled_from_debt, transfer_tokens,
 };
-use crate::instructions::utils::mint_and_freeze_token;
 
 
 #[derive(Accounts)]
 pub struct Lend<'info> {
-    
     #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
         ],
         bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
     )]
     pub pool: Box<Account<'info, Pool>>,
 
@@ -33,12 +49,31 @@ pub struct Lend<'info> {
     )]
     pub pool_authority: AccountInfo<'info>,
 
+    /// CHECK: Read only authority, mint authority of the shared cash mint
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub amm_authority: AccountInfo<'info>,
+
+    #[account(
+        address = pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
     #[account(
         mut,
         associated_token::mint = pool.mint_a,
         associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub pool_account_a: Box<Account<'info, TokenAccount>>,
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Validated against pool.oracle when reading the price
+    pub oracle: AccountInfo<'info>,
 
     #[account(
         mut,
@@ -51,22 +86,23 @@ pub struct Lend<'info> {
         mint::decimals = 6,
         mint::authority = pool_authority,        // mint authority 是 pool_authority
         mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::token_program = receipt_token_program,
     )]
-    pub lending_receipt_token_mint: Box<Account<'info, Mint>>,
+    pub lending_receipt_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         seeds = [
             pool.amm.as_ref(),
-            pool.mint_a.key().as_ref(),
             CASH_TOKEN_SEED,
         ],
         bump,
         mint::decimals = 6,
-        mint::authority = pool_authority,        // mint authority 是 pool_authority
-        mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::authority = amm_authority,        // mint authority 是 amm_authority
+        mint::freeze_authority = amm_authority, // freeze authority 也是 amm_authority
+        mint::token_program = token_program,
     )]
-    pub cash_token_mint: Box<Account<'info, Mint>>,
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     pub lender: Signer<'info>,
 
@@ -74,8 +110,9 @@ pub struct Lend<'info> {
         mut,
         associated_token::mint = pool.mint_a,
         associated_token::authority = lender,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub lender_token_a: Box<Account<'info, TokenAccount>>,
+    pub lender_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: Read only authority
     #[account(
@@ -88,52 +125,130 @@ pub struct Lend<'info> {
     )]
     pub lender_authority: AccountInfo<'info>,
 
+    /// CHECK: Receipt holder, checked against the pool's receipt mode
+    #[account(
+        constraint = receipt_owner.key()
+            == pool.collateral()?.receipt_owner(lender.key(), lender_authority.key())
+            @ FallError::InvalidReceiptOwner,
+    )]
+    pub receipt_owner: AccountInfo<'info>,
+
     #[account(
         init_if_needed,
         payer = payer,
         associated_token::mint = lending_receipt_token_mint,
-        associated_token::authority = lender_authority,
+        associated_token::authority = receipt_owner,
+        associated_token::token_program = receipt_token_program,
     )]
-    pub lender_lend_receipt_token: Box<Account<'info, TokenAccount>>,
+    pub lender_lend_receipt_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = payer,
         associated_token::mint = cash_token_mint,
         associated_token::authority = lender,
+        associated_token::token_program = token_program,
+    )]
+    pub lender_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// cash 池金库, 接收手续费
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = amm_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_account_cash: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = LenderPosition::LEN,
+        seeds = [
+            pool.key().as_ref(),
+            lender.key().as_ref(),
+            LENDER_POSITION_SEED,
+        ],
+        bump,
     )]
-    pub lender_cash_token: Box<Account<'info, TokenAccount>>,
+    pub lender_position: Box<Account<'info, LenderPosition>>,
 
     /// The account paying for all rents
     #[account(mut)]
     pub payer: Signer<'info>,
 
     /// Solana ecosystem accounts
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub receipt_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn lend(ctx: Context<Lend>,lender_lending_amount: u64,) -> Result<()> {
-    //  todo: 限制lender_lending_amount最小额度
+    require_not_paused(
+        &ctx.accounts.amm,
+        &ctx.accounts.pool,
+        PAUSE_DEPOSITS | PAUSE_MINTING,
+    )?;
+
      require!(
          ctx.accounts.lender_token_a.amount >= lender_lending_amount,
-         LendError::InsufficientBalance
+         FallError::InsufficientBalance
      );
+    check_deposit_limits(
+        ctx.accounts.pool.deposit_limits()?,
+        lender_lending_amount,
+        ctx.accounts.lender_position.collateral_amount.saturating_add(lender_lending_amount),
+        ctx.accounts.pool_account_a.amount.saturating_add(lender_lending_amount),
+    )?;
+
+    let price = get_price(&ctx.accounts.pool, &ctx.accounts.oracle)?;
+    let collateral = collateral_value(ctx.accounts.pool_account_a.amount, price)?;
+    accrue_interest(ctx.accounts.pool.collateral_mut()?, collateral)?;
  
     //  1. 转移 token A 到借贷池
-    token::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.lender_token_a.to_account_info(),
-                to: ctx.accounts.pool_account_a.to_account_info(),
-                authority: ctx.accounts.lender.to_account_info(),
-            },
-        ),
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.lender_token_a,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.lender.to_account_info(),
+        &[],
         lender_lending_amount,
     )?;
 
+    // 1.1 Token-2022 转账手续费会扣减到账数量, 以池子实际收到的数量记账
+    let balance_before = ctx.accounts.pool_account_a.amount;
+    ctx.accounts.pool_account_a.reload()?;
+    let received_amount = ctx
+        .accounts
+        .pool_account_a
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    require!(received_amount > 0, FallError::DepositTooSmall);
+
      // 2 铸造 lender_lend_receipt_token
      let authority_seeds = &[
          &ctx.accounts.pool.amm.to_bytes(),
@@ -142,32 +257,128 @@ pub fn lend(ctx: Context<Lend>,lender_lending_amount: u64,) -> Result<()> {
          &[ctx.bumps.pool_authority],
      ];
      let signer_seeds = &[&authority_seeds[..]];
-     mint_and_freeze_token(
-        &ctx.accounts.token_program,
-        &ctx.accounts.lending_receipt_token_mint,
-        &ctx.accounts.lender_lend_receipt_token,
-        &ctx.accounts.pool_authority,
-        signer_seeds,
-        lender_lending_amount,
-    )?;
+    if ctx.accounts.pool.collateral()?.non_transferable_receipts {
+        mint_token(
+            &ctx.accounts.receipt_token_program,
+            &ctx.accounts.lending_receipt_token_mint,
+            &ctx.accounts.lender_lend_receipt_token,
+            &ctx.accounts.pool_authority,
+            signer_seeds,
+            received_amount,
+        )?;
+    } else {
+        mint_and_freeze_token(
+            &ctx.accounts.receipt_token_program,
+            &ctx.accounts.lending_receipt_token_mint,
+            &ctx.accounts.lender_lend_receipt_token,
+            &ctx.accounts.pool_authority,
+            signer_seeds,
+            received_amount,
+        )?;
+    }
     
-    // 3 铸造 cash
-    mint_and_freeze_token(
-        &ctx.accounts.token_program,
-        &ctx.accounts.cash_token_mint,
-        &ctx.accounts.lender_cash_token,
-        &ctx.accounts.pool_authority,
-        signer_seeds,
-        lender_lending_amount*100/120,
+    // 3 铸造 cash, 所有抵押池共用 amm 级别的 cash mint
+    let amm_key = ctx.accounts.amm.key();
+    let amm_authority_seeds = &[
+        amm_key.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.amm_authority],
+    ];
+    let amm_signer_seeds = &[&amm_authority_seeds[..]];
+    let cash_amount = mul_div(
+        collateral_value(received_amount, price)?,
+        RATIO_PRECISION,
+        ctx.accounts.pool.collateral()?.collateral_ratio,
     )?;
-       
+    check_debt_ceiling(ctx.accounts.pool.collateral()?, cash_amount)?;
+    let (fee, protocol_fee) = compute_fees(&ctx.accounts.amm, cash_amount)?;
+    // cash 由所有池子共用: 已被其他池子冻结的钱包铸造后保持冻结
+    if ctx.accounts.pool.collateral()?.freeze_cash || ctx.accounts.lender_cash_token.is_frozen() {
+        mint_and_freeze_token(
+            &ctx.accounts.token_program,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.lender_cash_token,
+            &ctx.accounts.amm_authority,
+            amm_signer_seeds,
+            cash_amount - fee,
+        )?;
+    } else {
+        mint_token(
+            &ctx.accounts.token_program,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.lender_cash_token,
+            &ctx.accounts.amm_authority,
+            amm_signer_seeds,
+            cash_amount - fee,
+        )?;
+    }
+
+    // 3.1 手续费: 流动性部分进入 cash 池, 协议部分进入协议金库
+    if fee > protocol_fee {
+        mint_token(
+            &ctx.accounts.token_program,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.pool_account_cash,
+            &ctx.accounts.amm_authority,
+            amm_signer_seeds,
+            fee - protocol_fee,
+        )?;
+    }
+    if protocol_fee > 0 {
+        mint_token(
+            &ctx.accounts.token_program,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.amm_authority,
+            amm_signer_seeds,
+            protocol_fee,
+        )?;
+    }
+    emit!(FeeCharged {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.lender.key(),
+        fee,
+        protocol_fee,
+    });
+
+    // 4 记录仓位
+    let scaled_debt = scaled_from_debt(cash_amount, ctx.accounts.pool.collateral()?.borrow_index)?;
+    let pool = &mut ctx.accounts.pool;
+    let state = pool.collateral_mut()?;
+    state.total_scaled_debt = state
+        .total_scaled_debt
+        .checked_add(scaled_debt)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_a_amount = pool
+        .token_a_amount
+        .checked_add(received_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_b_amount = pool
+        .token_b_amount
+        .checked_add(cash_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let position = &mut ctx.accounts.lender_position;
+    if position.lender == Pubkey::default() {
+        position.pool = ctx.accounts.pool.key();
+        position.lender = ctx.accounts.lender.key();
+        position.open_timestamp = now;
+        position.bump = ctx.bumps.lender_position;
+    }
+    position.collateral_amount = position
+        .collateral_amount
+        .checked_add(received_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.cash_debt = position
+        .cash_debt
+        .checked_add(cash_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.scaled_debt = position
+        .scaled_debt
+        .checked_add(scaled_debt)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.last_update_timestamp = now;
+
     Ok(())
 }
-
-#[error_code]
-pub enum LendError {
-    #[msg("Existing lending")]
-    ExistingLending,
-    #[msg("Insufficient balance")]
-    InsufficientBalance,
-}
\ No newline at end of file
@@ -1,76 +1,88 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{self, Mint, Token, TokenAccount, Transfer, Burn},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 use crate::{
-    constants::{CASH_TOKEN_SEED,AUTHORITY_SEED,SCASH_TOKEN_SEED,CASH_POOL},
-    state::Pool,
+    constants::{CASH_TOKEN_SEED,AUTHORITY_SEED,SCASH_TOKEN_SEED,CASH_POOL,PROTOCOL_FEE_SEED,PAUSE_DEPOSITS},
+    errors::FallError,
+    events::FeeCharged,
+    state::{Amm, Pool, PoolKind},
+};
+use crate::instructions::utils::{
+    cash_for_shares, check_deposit_limits, compute_fees, mint_token, require_not_paused,
+    shares_for_cash, transfer_tokens,
 };
-use crate::instructions::utils::mint_and_freeze_token;
 
 
 #[derive(Accounts)]
 pub struct LendCash<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
 
-    pub mint_a: Box<Account<'info, Mint>>,
-    
     #[account(
+        mut,
         seeds = [
             cash_pool.amm.as_ref(),
-            cash_pool.mint_a.key().as_ref(),
             CASH_POOL,
         ],
         bump,
+        has_one = amm,
+        constraint = cash_pool.kind() == PoolKind::Cash @ FallError::InvalidPoolKind,
     )]
     pub cash_pool: Box<Account<'info, Pool>>,
 
-    /// CHECK: Read only authority
+    /// CHECK: Read only authority, owns the cash vault and the scash mint
     #[account(
         seeds = [
             cash_pool.amm.as_ref(),
-            cash_pool.mint_a.key().as_ref(),
             AUTHORITY_SEED,
         ],
         bump,
     )]
-    pub pool_authority: AccountInfo<'info>,
+    pub amm_authority: AccountInfo<'info>,
 
     #[account(
         init_if_needed,
         payer = payer,
         associated_token::mint = cash_token_mint,
-        associated_token::authority = pool_authority,
+        associated_token::authority = amm_authority,
+        associated_token::token_program = token_program,
     )]
-    pub pool_account_cash: Box<Account<'info, TokenAccount>>,   
+    pub pool_account_cash: Box<InterfaceAccount<'info, TokenAccount>>,   
 
     #[account(
         mut,
         seeds = [
             cash_pool.amm.as_ref(),
-            mint_a.key().as_ref(),
             CASH_TOKEN_SEED,
         ],
         bump,
         mint::decimals = 6,
-        mint::authority = pool_authority,        // mint authority 是 pool_authority
-        mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::authority = amm_authority,        // mint authority 是 amm_authority
+        mint::freeze_authority = amm_authority, // freeze authority 也是 amm_authority
+        mint::token_program = token_program,
     )]
-    pub cash_token_mint: Box<Account<'info, Mint>>,
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         seeds = [
             cash_pool.amm.as_ref(),
-            mint_a.key().as_ref(),
             SCASH_TOKEN_SEED,
         ],
         bump,
         mint::decimals = 6,
-        mint::authority = pool_authority,        // mint authority 是 pool_authority
-        mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::authority = amm_authority,        // mint authority 是 amm_authority
+        mint::freeze_authority = amm_authority, // freeze authority 也是 amm_authority
+        mint::token_program = token_program,
     )]
-    pub s_cash_token_mint: Box<Account<'info, Mint>>,
+    pub s_cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     pub lender: Signer<'info>,
 
@@ -78,73 +90,309 @@ pub struct LendCash<'info> {
         mut,
         associated_token::mint = cash_token_mint,
         associated_token::authority = lender,
+        associated_token::token_program = token_program,
     )]
-    pub lender_cash_token: Box<Account<'info, TokenAccount>>,
+    pub lender_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = payer,
         associated_token::mint = s_cash_token_mint,
         associated_token::authority = lender,
+        associated_token::token_program = token_program,
     )]
-    pub lender_scash_token: Box<Account<'info, TokenAccount>>,
+    pub lender_scash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// The account paying for all rents
     #[account(mut)]
     pub payer: Signer<'info>,
 
     /// Solana ecosystem accounts
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn lend_cash(ctx: Context<LendCash>,lender_lending_amount: u64,) -> Result<()> {
-    //  todo: 限制lender_lending_amount最小额度
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.cash_pool, PAUSE_DEPOSITS)?;
+
      require!(
          ctx.accounts.lender_cash_token.amount >= lender_lending_amount,
-         LendError::InsufficientBalance
+         FallError::InsufficientBalance
      );
+    let position_value = cash_for_shares(
+        ctx.accounts.lender_scash_token.amount,
+        ctx.accounts.pool_account_cash.amount,
+        ctx.accounts.s_cash_token_mint.supply,
+    )?;
+    check_deposit_limits(
+        ctx.accounts.cash_pool.deposit_limits()?,
+        lender_lending_amount,
+        position_value.saturating_add(lender_lending_amount),
+        ctx.accounts.pool_account_cash.amount.saturating_add(lender_lending_amount),
+    )?;
+
+    // 扣除手续费后按存款前的汇率计算 scash 份额, 流动性手续费留在池中
+    let (fee, protocol_fee) = compute_fees(&ctx.accounts.amm, lender_lending_amount)?;
+    let scash_amount = shares_for_cash(
+        lender_lending_amount - fee,
+        ctx.accounts.pool_account_cash.amount,
+        ctx.accounts.s_cash_token_mint.supply,
+    )?;
+    require!(scash_amount > 0, FallError::DepositTooSmall);
  
     //  1. 转移 token A 到借贷池
-    token::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.lender_cash_token.to_account_info(),
-                to: ctx.accounts.pool_account_cash.to_account_info(),
-                authority: ctx.accounts.lender.to_account_info(),
-            },
-        ),
-        lender_lending_amount,
+    transfer_tokens(
+        &ctx.accounts.token_program,
+        &ctx.accounts.lender_cash_token,
+        &ctx.accounts.pool_account_cash,
+        &ctx.accounts.cash_token_mint,
+        &ctx.accounts.lender.to_account_info(),
+        &[],
+        lender_lending_amount - protocol_fee,
     )?;
 
+    if protocol_fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.lender_cash_token,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.lender.to_account_info(),
+            &[],
+            protocol_fee,
+        )?;
+    }
+    emit!(FeeCharged {
+        pool: ctx.accounts.cash_pool.key(),
+        user: ctx.accounts.lender.key(),
+        fee,
+        protocol_fee,
+    });
+
      // 2 铸造 scash
      let authority_seeds = &[
          &ctx.accounts.cash_pool.amm.to_bytes(),
-         &ctx.accounts.cash_pool.mint_a.key().to_bytes(),
          AUTHORITY_SEED,
-         &[ctx.bumps.pool_authority],
+         &[ctx.bumps.amm_authority],
      ];
      let signer_seeds = &[&authority_seeds[..]];
     
     // 3 铸造 scash token
-    mint_and_freeze_token(
+    mint_token(
         &ctx.accounts.token_program,
         &ctx.accounts.s_cash_token_mint,
         &ctx.accounts.lender_scash_token,
-        &ctx.accounts.pool_authority,
+        &ctx.accounts.amm_authority,
         signer_seeds,
-        lender_lending_amount,
+        scash_amount,
     )?;
+
+    // 4 记录质押中的 cash 总量 (含已计入池子的利息与流动性手续费)
+    ctx.accounts.cash_pool.token_b_amount = ctx
+        .accounts
+        .pool_account_cash
+        .amount
+        .checked_add(lender_lending_amount - protocol_fee)
+        .ok_or(FallError::ArithmeticOverflow)?;
  
     Ok(())
 }
 
-#[error_code]
-pub enum LendError {
-    #[msg("Existing lending")]
-    ExistingLending,
-    #[msg("Insufficient balance")]
-    InsufficientBalance,
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{solana_program::entrypoint::ProgramResult, InstructionData, ToAccountMetas};
+    use anchor_spl::{associated_token, token_2022::spl_token_2022};
+    use crate::{
+        constants::{AUTHORITY_SEED, CASH_POOL, CASH_TOKEN_SEED, PROTOCOL_FEE_SEED, SCASH_TOKEN_SEED},
+        state::{Amm, Pool, PoolState},
+        test_utils::Bank,
+    };
+    use super::*;
+
+    struct CashPool {
+        amm: Pubkey,
+        cash_pool: Pubkey,
+        amm_authority: Pubkey,
+        pool_account_cash: Pubkey,
+        cash_token_mint: Pubkey,
+        s_cash_token_mint: Pubkey,
+        protocol_fee_authority: Pubkey,
+        protocol_fee_vault: Pubkey,
+        lender: Pubkey,
+        lender_cash_token: Pubkey,
+        lender_scash_token: Pubkey,
+    }
+
+    fn pda(seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &crate::ID).0
+    }
+
+    /// 按 create_amm / create_cash_pool 的布局准备账户
+    fn setup(bank: &mut Bank, lender_cash: u64) -> CashPool {
+        let token_program = spl_token_2022::ID;
+        let id = Pubkey::new_unique();
+        let amm = pda(&[id.as_ref()]);
+        let cash_pool = pda(&[amm.as_ref(), CASH_POOL]);
+        let amm_authority = pda(&[amm.as_ref(), AUTHORITY_SEED]);
+        let cash_token_mint = pda(&[amm.as_ref(), CASH_TOKEN_SEED]);
+        let s_cash_token_mint = pda(&[amm.as_ref(), SCASH_TOKEN_SEED]);
+        let protocol_fee_authority = pda(&[amm.as_ref(), PROTOCOL_FEE_SEED]);
+        let lender = Pubkey::new_unique();
+
+        bank.set_account(
+            amm,
+            &Amm {
+                id,
+                liquidity_fee: 30,
+                protocol_fee_percentage: 2000,
+                ..Default::default()
+            },
+            Amm::LEN,
+        );
+        bank.set_account(
+            cash_pool,
+            &Pool {
+                amm,
+                mint_a: cash_token_mint,
+                mint_a_decimals: 6,
+                state: PoolState::Cash(crate::state::CashPool::default()),
+                ..Default::default()
+            },
+            Pool::LEN,
+        );
+        bank.set_mint(cash_token_mint, token_program, amm_authority, lender_cash, 6);
+        bank.set_mint(s_cash_token_mint, token_program, amm_authority, 0, 6);
+        bank.set_wallet(lender);
+        CashPool {
+            amm,
+            cash_pool,
+            amm_authority,
+            pool_account_cash: bank.set_ata(amm_authority, cash_token_mint, token_program, 0),
+            cash_token_mint,
+            s_cash_token_mint,
+            protocol_fee_authority,
+            protocol_fee_vault: bank.set_ata(protocol_fee_authority, cash_token_mint, token_program, 0),
+            lender,
+            lender_cash_token: bank.set_ata(lender, cash_token_mint, token_program, lender_cash),
+            lender_scash_token: bank.set_ata(lender, s_cash_token_mint, token_program, 0),
+        }
+    }
+
+    fn lend(bank: &mut Bank, pool: &CashPool, amount: u64) -> ProgramResult {
+        let metas = crate::accounts::LendCash {
+            amm: pool.amm,
+            cash_pool: pool.cash_pool,
+            amm_authority: pool.amm_authority,
+            pool_account_cash: pool.pool_account_cash,
+            cash_token_mint: pool.cash_token_mint,
+            s_cash_token_mint: pool.s_cash_token_mint,
+            lender: pool.lender,
+            lender_cash_token: pool.lender_cash_token,
+            lender_scash_token: pool.lender_scash_token,
+            protocol_fee_authority: pool.protocol_fee_authority,
+            protocol_fee_vault: pool.protocol_fee_vault,
+            payer: pool.lender,
+            token_program: spl_token_2022::ID,
+            associated_token_program: associated_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None);
+        let data = crate::instruction::LendCash { user_lending_amount: amount }.data();
+        bank.process(metas, data)
+    }
+
+    fn redeem(bank: &mut Bank, pool: &CashPool, amount: u64) -> ProgramResult {
+        let metas = crate::accounts::RedeemCash {
+            amm: pool.amm,
+            cash_pool: pool.cash_pool,
+            amm_authority: pool.amm_authority,
+            pool_account_cash: pool.pool_account_cash,
+            cash_token_mint: pool.cash_token_mint,
+            s_cash_token_mint: pool.s_cash_token_mint,
+            lender: pool.lender,
+            lender_cash_token: pool.lender_cash_token,
+            lender_scash_token: pool.lender_scash_token,
+            protocol_fee_authority: pool.protocol_fee_authority,
+            protocol_fee_vault: pool.protocol_fee_vault,
+            payer: pool.lender,
+            token_program: spl_token_2022::ID,
+            associated_token_program: associated_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None);
+        let data = crate::instruction::RedeemCash { amount }.data();
+        bank.process(metas, data)
+    }
+
+    #[test]
+    fn lend_cash_then_redeem_cash() {
+        let mut bank = Bank::new();
+        let pool = setup(&mut bank, 1_000_000);
+
+        // 存入 1 cash: 手续费 0.3%, 其中 20% 进入协议金库
+        lend(&mut bank, &pool, 1_000_000).unwrap();
+        assert_eq!(bank.token_amount(&pool.lender_cash_token), 0);
+        assert_eq!(bank.token_amount(&pool.protocol_fee_vault), 600);
+        assert_eq!(bank.token_amount(&pool.pool_account_cash), 999_400);
+        let shares = bank.token_amount(&pool.lender_scash_token);
+        assert_eq!(shares, 997_000);
+        assert_eq!(bank.account::<Pool>(&pool.cash_pool).token_b_amount, 999_400);
+
+        // 全部赎回, 流动性手续费归 sCash 持有人
+        redeem(&mut bank, &pool, shares).unwrap();
+        assert_eq!(bank.token_amount(&pool.lender_scash_token), 0);
+        assert_eq!(bank.mint_supply(&pool.s_cash_token_mint), 0);
+        let returned = bank.token_amount(&pool.lender_cash_token);
+        assert!(returned > 990_000 && returned < 1_000_000);
+        assert_eq!(
+            returned
+                + bank.token_amount(&pool.protocol_fee_vault)
+                + bank.token_amount(&pool.pool_account_cash),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn scash_holders_receive_pool_income() {
+        let mut bank = Bank::new();
+        let pool = setup(&mut bank, 1_000_000);
+        lend(&mut bank, &pool, 1_000_000).unwrap();
+        let shares = bank.token_amount(&pool.lender_scash_token);
+
+        // lend/redeem/liquidate/flash_mint_repay 的利息与手续费进入同一个金库
+        let vault = bank.token_amount(&pool.pool_account_cash);
+        bank.set_ata(pool.amm_authority, pool.cash_token_mint, spl_token_2022::ID, vault + 100_000);
+
+        redeem(&mut bank, &pool, shares).unwrap();
+        assert!(bank.token_amount(&pool.lender_cash_token) > 1_090_000);
+    }
+
+    #[test]
+    fn redeem_cash_rejects_more_than_balance() {
+        let mut bank = Bank::new();
+        let pool = setup(&mut bank, 1_000_000);
+        lend(&mut bank, &pool, 1_000_000).unwrap();
+        let shares = bank.token_amount(&pool.lender_scash_token);
+        assert!(redeem(&mut bank, &pool, shares + 1).is_err());
+    }
+}
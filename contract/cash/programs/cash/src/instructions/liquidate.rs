@@ -0,0 +1,386 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{self, Burn, Mint, TokenAccount, TokenInterface},
+    associated_token::AssociatedToken,
+};
+use crate::{
+    constants::{AUTHORITY_SEED, LENDING_TOKEN_SEED, CASH_TOKEN_SEED, LENDER_POSITION_SEED, RATIO_PRECISION, INDEX_PRECISION, PAUSE_WITHDRAWALS},
+    errors::FallError,
+    events::BadDebtWrittenOff,
+    oracle::{collateral_for_value, collateral_value, get_price},
+    state::{Amm, LenderPosition, Pool, PoolKind},
+};
+use crate::instructions::utils::{
+    accrue_interest, debt_from_scaled, freeze_token, is_healthy, mul_div, mul_div_ceil_u128,
+    require_not_paused, thaw_token, transfer_tokens,
+};
+
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    /// CHECK: Read only authority, mint authority of the shared cash mint
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub amm_authority: AccountInfo<'info>,
+
+    #[account(
+        address = pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = pool.mint_a,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Validated against pool.oracle when reading the price
+    pub oracle: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            LENDING_TOKEN_SEED,
+        ],
+        bump,
+        mint::decimals = 6,
+        mint::authority = pool_authority,        // mint authority 是 pool_authority
+        mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::token_program = receipt_token_program,
+    )]
+    pub lending_receipt_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            CASH_TOKEN_SEED,
+        ],
+        bump,
+        mint::decimals = 6,
+        mint::authority = amm_authority,        // mint authority 是 amm_authority
+        mint::freeze_authority = amm_authority, // freeze authority 也是 amm_authority
+        mint::token_program = token_program,
+    )]
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// cash 池金库, 接收利息
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = amm_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_account_cash: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Owner of the liquidated position
+    pub lender: AccountInfo<'info>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            pool.key().as_ref(),
+            lender.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub lender_authority: AccountInfo<'info>,
+
+    /// CHECK: Receipt holder, checked against the pool's receipt mode
+    #[account(
+        constraint = receipt_owner.key()
+            == pool.collateral()?.receipt_owner(lender.key(), lender_authority.key())
+            @ FallError::InvalidReceiptOwner,
+    )]
+    pub receipt_owner: AccountInfo<'info>,
+
+    /// NonTransferable 模式下 lender 可以销毁 receipt 并关闭账户, 清算时按需重建
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = lending_receipt_token_mint,
+        associated_token::authority = receipt_owner,
+        associated_token::token_program = receipt_token_program,
+    )]
+    pub lender_lend_receipt_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.key().as_ref(),
+            lender.key().as_ref(),
+            LENDER_POSITION_SEED,
+        ],
+        bump = lender_position.bump,
+    )]
+    pub lender_position: Box<Account<'info, LenderPosition>>,
+
+    pub liquidator: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program,
+    )]
+    pub liquidator_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = liquidator,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub liquidator_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub receipt_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn liquidate(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.pool, PAUSE_WITHDRAWALS)?;
+
+    let price = get_price(&ctx.accounts.pool, &ctx.accounts.oracle)?;
+    let collateral = collateral_value(ctx.accounts.pool_account_a.amount, price)?;
+    accrue_interest(ctx.accounts.pool.collateral_mut()?, collateral)?;
+
+    // 1. 校验仓位低于最低抵押率
+    let pool = ctx.accounts.pool.collateral()?;
+    let position = &ctx.accounts.lender_position;
+    let debt = debt_from_scaled(position.scaled_debt, pool.borrow_index)?;
+    require!(
+        debt > 0
+            && !is_healthy(
+                collateral_value(position.collateral_amount, price)?,
+                debt,
+                pool.min_collateral_ratio,
+            ),
+        FallError::PositionNotLiquidatable
+    );
+
+    // 2. 按 close factor 限制偿还数量
+    let max_repay = mul_div(debt, pool.close_factor, RATIO_PRECISION)?.max(1);
+    let repay_amount = repay_amount.min(max_repay);
+    require!(repay_amount > 0, FallError::DepositTooSmall);
+
+    let scaled_repaid = if repay_amount >= debt {
+        position.scaled_debt
+    } else {
+        ((repay_amount as u128) * INDEX_PRECISION / pool.borrow_index).min(position.scaled_debt)
+    };
+    let principal_repaid = if scaled_repaid == position.scaled_debt {
+        position.cash_debt
+    } else {
+        let principal = mul_div_ceil_u128(
+            position.cash_debt as u128,
+            scaled_repaid,
+            position.scaled_debt,
+        )?;
+        u64::try_from(principal).map_err(|_| error!(FallError::NumberOverflow))?
+    }
+    .min(repay_amount);
+    let interest = repay_amount - principal_repaid;
+
+    // 3. 抵押物价值 = 偿还数量 * (1 + 清算奖励)
+    let seized_value = mul_div(
+        repay_amount,
+        RATIO_PRECISION + pool.liquidation_bonus,
+        RATIO_PRECISION,
+    )?;
+    let seized_collateral = collateral_for_value(seized_value, price)?
+        .min(position.collateral_amount);
+
+    // 4. 清算人偿还 cash: 本金销毁, 利息转入 cash 池
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.cash_token_mint.to_account_info(),
+                from: ctx.accounts.liquidator_cash_token.to_account_info(),
+                authority: ctx.accounts.liquidator.to_account_info(),
+            },
+        ),
+        principal_repaid,
+    )?;
+
+    if interest > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.liquidator_cash_token,
+            &ctx.accounts.pool_account_cash,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.liquidator.to_account_info(),
+            &[],
+            interest,
+        )?;
+    }
+
+    // 5. 解冻、销毁对应的 lending receipt token, 剩余部分重新冻结
+    let lender_authority_seeds = &[
+        &ctx.accounts.pool.key().to_bytes(),
+        &ctx.accounts.lender.key().to_bytes(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.lender_authority],
+    ];
+    let lender_signer_seeds = &[&lender_authority_seeds[..]];
+
+    let pool_authority_seeds = &[
+        ctx.accounts.pool.amm.as_ref(),
+        ctx.accounts.pool.mint_a.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.pool_authority],
+    ];
+    let pool_signer_seeds = &[&pool_authority_seeds[..]];
+
+    // NonTransferable receipt 由 pool_authority 以 permanent delegate 身份销毁
+    let (receipt_burn_authority, receipt_burn_seeds): (AccountInfo, &[&[&[u8]]]) =
+        if ctx.accounts.pool.collateral()?.non_transferable_receipts {
+            (ctx.accounts.pool_authority.to_account_info(), pool_signer_seeds)
+        } else {
+            (ctx.accounts.lender_authority.to_account_info(), lender_signer_seeds)
+        };
+    // 仓位以 LenderPosition 为准; NonTransferable 模式下 lender 可能已自行销毁部分 receipt
+    let receipt_burn = seized_collateral.min(ctx.accounts.lender_lend_receipt_token.amount);
+    thaw_token(
+        &ctx.accounts.receipt_token_program,
+        &ctx.accounts.lending_receipt_token_mint,
+        &ctx.accounts.lender_lend_receipt_token,
+        &ctx.accounts.pool_authority,
+        pool_signer_seeds,
+    )?;
+    if receipt_burn > 0 {
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.receipt_token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.lending_receipt_token_mint.to_account_info(),
+                    from: ctx.accounts.lender_lend_receipt_token.to_account_info(),
+                    authority: receipt_burn_authority,
+                },
+                receipt_burn_seeds,
+            ),
+            receipt_burn,
+        )?;
+    }
+    if !ctx.accounts.pool.collateral()?.non_transferable_receipts {
+        freeze_token(
+            &ctx.accounts.receipt_token_program,
+            &ctx.accounts.lending_receipt_token_mint,
+            &ctx.accounts.lender_lend_receipt_token,
+            &ctx.accounts.pool_authority,
+            pool_signer_seeds,
+        )?;
+    }
+
+    // 6. 抵押物转给清算人
+
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.liquidator_token_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.pool_authority.to_account_info(),
+        pool_signer_seeds,
+        seized_collateral,
+    )?;
+
+    // 7. 更新仓位
+    let pool = &mut ctx.accounts.pool;
+    let state = pool.collateral_mut()?;
+    state.total_scaled_debt = state
+        .total_scaled_debt
+        .checked_sub(scaled_repaid)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_a_amount = pool
+        .token_a_amount
+        .checked_sub(seized_collateral)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_b_amount = pool
+        .token_b_amount
+        .checked_sub(principal_repaid)
+        .ok_or(FallError::ArithmeticOverflow)?;
+
+    let position = &mut ctx.accounts.lender_position;
+    position.collateral_amount = position
+        .collateral_amount
+        .checked_sub(seized_collateral)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.cash_debt = position
+        .cash_debt
+        .checked_sub(principal_repaid)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.scaled_debt = position
+        .scaled_debt
+        .checked_sub(scaled_repaid)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.last_update_timestamp = Clock::get()?.unix_timestamp;
+
+    // 8. 抵押物已被清空仍有剩余债务时核销坏账, 由全体 cash 持有人分摊
+    if position.collateral_amount == 0 && position.scaled_debt > 0 {
+        let state = pool.collateral_mut()?;
+        let bad_debt = debt_from_scaled(position.scaled_debt, state.borrow_index)?;
+        state.total_scaled_debt = state
+            .total_scaled_debt
+            .checked_sub(position.scaled_debt)
+            .ok_or(FallError::ArithmeticOverflow)?;
+        pool.token_b_amount = pool.token_b_amount.saturating_sub(position.cash_debt);
+        position.scaled_debt = 0;
+        position.cash_debt = 0;
+        emit!(BadDebtWrittenOff {
+            pool: pool.key(),
+            lender: position.lender,
+            debt: bad_debt,
+        });
+    }
+
+    Ok(())
+}
@@ -1,17 +1,61 @@
+mod accept_admin;
+mod collect_protocol_fees;
 mod create_amm;
 mod create_pool_1;
+mod create_psm_pool;
+mod create_swap_pool;
+mod deposit_liquidity;
+mod flash_loan;
+mod flash_mint;
+mod propose_admin;
+mod psm;
+mod ramp_amp;
 mod redeem;
 mod lend;
+mod liquidate;
 mod lendcash;
 mod redeemcash;
 mod create_cash_pool;
+mod set_amm_pause;
+mod set_guardian;
+mod set_interest_rate_model;
+#[cfg(feature = "mock-oracle")]
+mod set_mock_price;
+mod set_oracle;
+mod set_pool_pause;
+mod swap_exact_in;
+mod update_amm_config;
+mod update_pool_config;
 mod utils;
+mod withdraw_liquidity;
 
+pub use accept_admin::*;
+pub use collect_protocol_fees::*;
 pub use create_amm::*;  
 pub use create_pool_1::*;
+pub use create_psm_pool::*;
 pub use create_cash_pool::*;
+pub use create_swap_pool::*;
+pub use deposit_liquidity::*;
+pub use flash_loan::*;
+pub use flash_mint::*;
+pub use propose_admin::*;
+pub use psm::*;
+pub use ramp_amp::*;
 pub use redeem::*;
 pub use lend::*;
+pub use liquidate::*;
 pub use lendcash::*;
 pub use redeemcash::*;
+pub use set_amm_pause::*;
+pub use set_guardian::*;
+pub use set_interest_rate_model::*;
+#[cfg(feature = "mock-oracle")]
+pub use set_mock_price::*;
+pub use set_oracle::*;
+pub use set_pool_pause::*;
+pub use swap_exact_in::*;
+pub use update_amm_config::*;
+pub use update_pool_config::*;
 pub use utils::*;
+pub use withdraw_liquidity::*;
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+use crate::state::Amm;
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+}
+
+
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    ctx.accounts.amm.pending_admin = new_admin;
+
+    Ok(())
+}
+
+pub fn cancel_admin_proposal(ctx: Context<ProposeAdmin>) -> Result<()> {
+    ctx.accounts.amm.pending_admin = Pubkey::default();
+
+    Ok(())
+}
@@ -0,0 +1,300 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{self, Burn, Mint, TokenAccount, TokenInterface},
+};
+use crate::{
+    constants::{
+        AUTHORITY_SEED, CASH_DECIMALS, CASH_TOKEN_SEED, PAUSE_DEPOSITS, PAUSE_MINTING,
+        PAUSE_WITHDRAWALS, PROTOCOL_FEE_SEED, RATIO_PRECISION,
+    },
+    errors::FallError,
+    events::FeeCharged,
+    state::{Amm, Pool, PoolKind},
+};
+use crate::instructions::utils::{mint_token, mul_div_ceil, require_not_paused, transfer_tokens};
+
+#[derive(Accounts)]
+pub struct PsmSwap<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Psm @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority, owns the PSM vault
+    #[account(
+        seeds = [
+            pool.amm.as_ref(),
+            pool.mint_a.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        address = pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = pool.mint_a,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority, mint authority of the shared cash mint
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub amm_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            amm.key().as_ref(),
+            CASH_TOKEN_SEED,
+        ],
+        bump,
+        mint::authority = amm_authority,
+        mint::token_program = token_program,
+    )]
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::token_program = collateral_token_program,
+    )]
+    pub user_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = cash_token_mint,
+        token::token_program = token_program,
+    )]
+    pub user_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// 按精度换算数量, rounded down
+fn convert_decimals(amount: u64, from_decimals: u8, to_decimals: u8) -> Result<u64> {
+    let result = if to_decimals >= from_decimals {
+        let scale = 10u128
+            .checked_pow((to_decimals - from_decimals) as u32)
+            .ok_or(FallError::ArithmeticOverflow)?;
+        (amount as u128)
+            .checked_mul(scale)
+            .ok_or(FallError::ArithmeticOverflow)?
+    } else {
+        let scale = 10u128
+            .checked_pow((from_decimals - to_decimals) as u32)
+            .ok_or(FallError::ArithmeticOverflow)?;
+        amount as u128 / scale
+    };
+    u64::try_from(result).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// 存入稳定币, 1:1 铸造 cash, 手续费以 cash 铸造到协议金库
+pub fn psm_mint(ctx: Context<PsmSwap>, amount: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.pool, PAUSE_DEPOSITS | PAUSE_MINTING)?;
+    require!(amount > 0, FallError::DepositTooSmall);
+
+    // 1. 稳定币转入 PSM 金库, 以实际收到的数量计算
+    let vault_before = ctx.accounts.pool_account_a.amount;
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.user_token_a,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.user.to_account_info(),
+        &[],
+        amount,
+    )?;
+    ctx.accounts.pool_account_a.reload()?;
+    let received = ctx.accounts.pool_account_a.amount.saturating_sub(vault_before);
+
+    // 2. 换算为 cash 并检查该稳定币的 debt ceiling
+    let cash_amount = convert_decimals(received, ctx.accounts.pool.mint_a_decimals, CASH_DECIMALS)?;
+    require!(cash_amount > 0, FallError::DepositTooSmall);
+    let minted = ctx.accounts.pool.token_b_amount
+        .checked_add(cash_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    let debt_ceiling = ctx.accounts.pool.psm()?.debt_ceiling;
+    require!(
+        debt_ceiling == 0 || minted <= debt_ceiling,
+        FallError::DebtCeilingExceeded
+    );
+    let fee = mul_div_ceil(cash_amount, ctx.accounts.pool.psm()?.psm_mint_fee, RATIO_PRECISION)?;
+
+    // 3. 铸造 cash
+    let amm_key = ctx.accounts.amm.key();
+    let authority_seeds = &[
+        amm_key.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.amm_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    mint_token(
+        &ctx.accounts.token_program,
+        &ctx.accounts.cash_token_mint,
+        &ctx.accounts.user_cash_token,
+        &ctx.accounts.amm_authority,
+        signer_seeds,
+        cash_amount - fee,
+    )?;
+    if fee > 0 {
+        mint_token(
+            &ctx.accounts.token_program,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.amm_authority,
+            signer_seeds,
+            fee,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.token_a_amount = pool.token_a_amount
+        .checked_add(received)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_b_amount = minted;
+    emit!(FeeCharged {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        fee,
+        protocol_fee: fee,
+    });
+
+    Ok(())
+}
+
+/// 销毁 cash, 1:1 赎回稳定币, 手续费以 cash 转入协议金库
+pub fn psm_redeem(ctx: Context<PsmSwap>, cash_amount: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.pool, PAUSE_WITHDRAWALS)?;
+    require!(cash_amount > 0, FallError::DepositTooSmall);
+    require!(
+        ctx.accounts.user_cash_token.amount >= cash_amount,
+        FallError::InsufficientBalance
+    );
+
+    // 1. 扣除手续费后换算为稳定币
+    let fee = mul_div_ceil(cash_amount, ctx.accounts.pool.psm()?.psm_redeem_fee, RATIO_PRECISION)?;
+    let burn_amount = cash_amount - fee;
+    let amount_a = convert_decimals(burn_amount, CASH_DECIMALS, ctx.accounts.pool.mint_a_decimals)?;
+    require!(amount_a > 0, FallError::DepositTooSmall);
+    require!(
+        burn_amount <= ctx.accounts.pool.token_b_amount
+            && amount_a <= ctx.accounts.pool_account_a.amount,
+        FallError::InsufficientLiquidity
+    );
+
+    // 2. 销毁 cash, 手续费转入协议金库
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.cash_token_mint.to_account_info(),
+                from: ctx.accounts.user_cash_token.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        burn_amount,
+    )?;
+    if fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.user_cash_token,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.user.to_account_info(),
+            &[],
+            fee,
+        )?;
+    }
+
+    // 3. 从 PSM 金库转出稳定币
+    let pool_authority_seeds = &[
+        ctx.accounts.pool.amm.as_ref(),
+        &ctx.accounts.mint_a.key().to_bytes(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.pool_authority],
+    ];
+    let pool_signer_seeds = &[&pool_authority_seeds[..]];
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.user_token_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.pool_authority.to_account_info(),
+        pool_signer_seeds,
+        amount_a,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.token_a_amount = pool
+        .token_a_amount
+        .checked_sub(amount_a)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_b_amount = pool
+        .token_b_amount
+        .checked_sub(burn_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    emit!(FeeCharged {
+        pool: pool.key(),
+        user: ctx.accounts.user.key(),
+        fee,
+        protocol_fee: fee,
+    });
+
+    Ok(())
+}
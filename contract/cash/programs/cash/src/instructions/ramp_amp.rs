@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::{MAX_AMP_CHANGE, MIN_RAMP_DURATION},
+    errors::FallError,
+    state::{Amm, CurveType, SwapPool},
+};
+use crate::instructions::utils::validate_amp;
+
+#[derive(Accounts)]
+pub struct RampAmp<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        has_one = amm,
+        constraint = swap_pool.curve_type == CurveType::StableSwap @ FallError::InvalidCurveType,
+    )]
+    pub swap_pool: Box<Account<'info, SwapPool>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+}
+
+/// 从当前放大系数线性过渡到 target_amp, 在 ramp_stop_timestamp 完成
+pub fn ramp_amp(ctx: Context<RampAmp>, target_amp: u64, ramp_stop_timestamp: i64) -> Result<()> {
+    validate_amp(target_amp)?;
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        ramp_stop_timestamp >= now.saturating_add(MIN_RAMP_DURATION),
+        FallError::InvalidAmpRamp
+    );
+
+    // 单次 ramp 的变化幅度受限, 避免价格曲线突变
+    let swap_pool = &mut ctx.accounts.swap_pool;
+    let current_amp = swap_pool.current_amp(now);
+    require!(
+        target_amp <= current_amp.saturating_mul(MAX_AMP_CHANGE)
+            && target_amp.saturating_mul(MAX_AMP_CHANGE) >= current_amp,
+        FallError::InvalidAmpRamp
+    );
+
+    swap_pool.initial_amp = current_amp;
+    swap_pool.target_amp = target_amp;
+    swap_pool.ramp_start_timestamp = now;
+    swap_pool.ramp_stop_timestamp = ramp_stop_timestamp;
+
+    Ok(())
+}
+
+/// 停止进行中的 ramp, 放大系数固定为当前值
+pub fn stop_ramp_amp(ctx: Context<RampAmp>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let swap_pool = &mut ctx.accounts.swap_pool;
+    let current_amp = swap_pool.current_amp(now);
+
+    swap_pool.initial_amp = current_amp;
+    swap_pool.target_amp = current_amp;
+    swap_pool.ramp_start_timestamp = now;
+    swap_pool.ramp_stop_timestamp = now;
+
+    Ok(())
+}
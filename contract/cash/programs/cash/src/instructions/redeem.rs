@@ -1,11 +1,18 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token::{self, Mint, Token, TokenAccount, Transfer, Burn},
+    token_interface::{self, Burn, Mint, TokenAccount, TokenInterface},
     associated_token::AssociatedToken,
 };
 use crate::{
-    constants::{AUTHORITY_SEED, LENDING_TOKEN_SEED, CASH_TOKEN_SEED},
-    state::Pool,
+    constants::{AUTHORITY_SEED, LENDING_TOKEN_SEED, CASH_TOKEN_SEED, LENDER_POSITION_SEED, PROTOCOL_FEE_SEED, PAUSE_WITHDRAWALS},
+    errors::FallError,
+    events::FeeCharged,
+    oracle::{collateral_value, get_price},
+    state::{Amm, LenderPosition, Pool, PoolKind},
+};
+use crate::instructions::utils::{
+    accrue_interest, compute_fees, debt_from_scaled, is_healthy, mul_div_ceil, mul_div_ceil_u128,
+    freeze_token, require_not_paused, thaw_token, transfer_tokens,
 };
 
 
@@ -13,11 +20,22 @@ use crate::{
 #[derive(Accounts)]
 pub struct Redeem<'info> {
     #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
         seeds = [
             pool.amm.as_ref(),
             pool.mint_a.key().as_ref(),
         ],
         bump,
+        has_one = amm,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
     )]
     pub pool: Box<Account<'info, Pool>>,
 
@@ -32,14 +50,31 @@ pub struct Redeem<'info> {
     )]
     pub pool_authority: AccountInfo<'info>,
 
-    pub mint_a: Box<Account<'info, Mint>>,
+    /// CHECK: Read only authority, mint authority of the shared cash mint
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub amm_authority: AccountInfo<'info>,
+
+    #[account(
+        address = pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = pool.mint_a,
         associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub pool_account_a: Box<Account<'info, TokenAccount>>,
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Validated against pool.oracle when reading the price
+    pub oracle: AccountInfo<'info>,
 
     #[account(
         mut,
@@ -52,8 +87,9 @@ pub struct Redeem<'info> {
         mint::decimals = 6,
         mint::authority = pool_authority,        // mint authority 是 pool_authority
         mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::token_program = receipt_token_program,
     )]
-    pub lending_receipt_token_mint: Box<Account<'info, Mint>>,
+    pub lending_receipt_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     pub lender: Signer<'info>,
 
@@ -61,8 +97,9 @@ pub struct Redeem<'info> {
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = lender,
+        associated_token::token_program = collateral_token_program,
     )]
-    pub lender_token_a: Box<Account<'info, TokenAccount>>,
+    pub lender_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// CHECK: Read only authority
     #[account(
@@ -75,48 +112,127 @@ pub struct Redeem<'info> {
     )]
     pub lender_authority: AccountInfo<'info>,
 
+    /// CHECK: Receipt holder, checked against the pool's receipt mode
+    #[account(
+        constraint = receipt_owner.key()
+            == pool.collateral()?.receipt_owner(lender.key(), lender_authority.key())
+            @ FallError::InvalidReceiptOwner,
+    )]
+    pub receipt_owner: AccountInfo<'info>,
+
     #[account(
         mut,
         associated_token::mint = lending_receipt_token_mint,
-        associated_token::authority = lender_authority,
+        associated_token::authority = receipt_owner,
+        associated_token::token_program = receipt_token_program,
     )]
-    pub lender_lend_receipt_token: Box<Account<'info, TokenAccount>>,
+    pub lender_lend_receipt_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
 
     #[account(
         mut,
         seeds = [
             pool.amm.as_ref(),
-            pool.mint_a.key().as_ref(),
             CASH_TOKEN_SEED,
         ],
         bump,
         mint::decimals = 6,
-        mint::authority = pool_authority,        // mint authority 是 pool_authority
-        mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::authority = amm_authority,        // mint authority 是 amm_authority
+        mint::freeze_authority = amm_authority, // freeze authority 也是 amm_authority
+        mint::token_program = token_program,
     )]
-    pub cash_token_mint: Box<Account<'info, Mint>>,
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = cash_token_mint,
         associated_token::authority = lender,
+        associated_token::token_program = token_program,
+    )]
+    pub lender_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// cash 池金库, 接收利息和手续费
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = amm_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_account_cash: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = token_program,
     )]
-    pub lender_cash_token: Box<Account<'info, TokenAccount>>,
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            pool.key().as_ref(),
+            lender.key().as_ref(),
+            LENDER_POSITION_SEED,
+        ],
+        bump = lender_position.bump,
+    )]
+    pub lender_position: Box<Account<'info, LenderPosition>>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
 
     // 4. Programs
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub receipt_token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 
 }
 
-pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
-    // 1. 解码 lending receipt token 数量
-    let lender_lending_receipt_amount = ctx.accounts.lender_lend_receipt_token.amount;
+pub fn redeem(ctx: Context<Redeem>, lender_lending_receipt_amount: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.pool, PAUSE_WITHDRAWALS)?;
+
+    let price = get_price(&ctx.accounts.pool, &ctx.accounts.oracle)?;
+    let collateral = collateral_value(ctx.accounts.pool_account_a.amount, price)?;
+    accrue_interest(ctx.accounts.pool.collateral_mut()?, collateral)?;
+
+    // 1. 按比例计算需要偿还的本金和利息
+    let position = &ctx.accounts.lender_position;
+    let collateral_amount = position.collateral_amount;
+    require!(lender_lending_receipt_amount > 0, FallError::DepositTooSmall);
+    require!(
+        lender_lending_receipt_amount <= collateral_amount,
+        FallError::InsufficientBalance
+    );
+    let (cash_debt, scaled_debt) = if lender_lending_receipt_amount == collateral_amount {
+        (position.cash_debt, position.scaled_debt)
+    } else {
+        (
+            mul_div_ceil(position.cash_debt, lender_lending_receipt_amount, collateral_amount)?,
+            mul_div_ceil_u128(
+                position.scaled_debt,
+                lender_lending_receipt_amount as u128,
+                collateral_amount as u128,
+            )?,
+        )
+    };
+    let repay_amount = debt_from_scaled(scaled_debt, ctx.accounts.pool.collateral()?.borrow_index)?;
+    let cash_debt = cash_debt.min(repay_amount);
+    let interest = repay_amount - cash_debt;
 
     let lender_authority_seeds = &[
         &ctx.accounts.pool.key().to_bytes(),
@@ -126,22 +242,73 @@ pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
     ];
     let signer_seeds = &[&lender_authority_seeds[..]];
 
-    // 2. 解冻、销毁 lending receipt token
-    token::burn(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Burn {
-                mint: ctx.accounts.lending_receipt_token_mint.to_account_info(),
-                from: ctx.accounts.lender_lend_receipt_token.to_account_info(),
-                authority: ctx.accounts.lender_authority.to_account_info(),
-            }, 
-            signer_seeds,
-        ),
-        lender_lending_receipt_amount,
+    let pool_authority_seeds = &[
+        ctx.accounts.pool.amm.as_ref(),
+        &ctx.accounts.mint_a.key().to_bytes(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.pool_authority],
+    ];
+    let pool_signer_seeds = &[&pool_authority_seeds[..]];
+
+    let amm_key = ctx.accounts.amm.key();
+    let amm_authority_seeds = &[
+        amm_key.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.amm_authority],
+    ];
+    let amm_signer_seeds = &[&amm_authority_seeds[..]];
+
+    // 2. 解冻、销毁 lending receipt token, 剩余部分重新冻结
+    // NonTransferable receipt 由 pool_authority 以 permanent delegate 身份销毁
+    let (receipt_burn_authority, receipt_burn_seeds): (AccountInfo, &[&[&[u8]]]) =
+        if ctx.accounts.pool.collateral()?.non_transferable_receipts {
+            (ctx.accounts.pool_authority.to_account_info(), pool_signer_seeds)
+        } else {
+            (ctx.accounts.lender_authority.to_account_info(), signer_seeds)
+        };
+    // 仓位以 LenderPosition 为准; NonTransferable 模式下 lender 可能已自行销毁部分 receipt
+    let receipt_burn = lender_lending_receipt_amount.min(ctx.accounts.lender_lend_receipt_token.amount);
+    thaw_token(
+        &ctx.accounts.receipt_token_program,
+        &ctx.accounts.lending_receipt_token_mint,
+        &ctx.accounts.lender_lend_receipt_token,
+        &ctx.accounts.pool_authority,
+        pool_signer_seeds,
     )?;
+    if receipt_burn > 0 {
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.receipt_token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.lending_receipt_token_mint.to_account_info(),
+                    from: ctx.accounts.lender_lend_receipt_token.to_account_info(),
+                    authority: receipt_burn_authority,
+                },
+                receipt_burn_seeds,
+            ),
+            receipt_burn,
+        )?;
+    }
+    if !ctx.accounts.pool.collateral()?.non_transferable_receipts {
+        freeze_token(
+            &ctx.accounts.receipt_token_program,
+            &ctx.accounts.lending_receipt_token_mint,
+            &ctx.accounts.lender_lend_receipt_token,
+            &ctx.accounts.pool_authority,
+            pool_signer_seeds,
+        )?;
+    }
 
-    // 3. 销毁 cash token
-    token::burn(
+    // 3. 销毁 cash token, 冻结的 cash 先解冻, 支付完成后重新冻结
+    let cash_frozen = ctx.accounts.lender_cash_token.is_frozen();
+    thaw_token(
+        &ctx.accounts.token_program,
+        &ctx.accounts.cash_token_mint,
+        &ctx.accounts.lender_cash_token,
+        &ctx.accounts.amm_authority,
+        amm_signer_seeds,
+    )?;
+    token_interface::burn(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Burn {
@@ -151,36 +318,118 @@ pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
             }, 
             signer_seeds,
         ),
-        lender_lending_receipt_amount * 100 / 120,
+        cash_debt,
     )?;
 
+    // 3.1 利息转入 cash 池
+    if interest > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.lender_cash_token,
+            &ctx.accounts.pool_account_cash,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.lender.to_account_info(),
+            &[],
+            interest,
+        )?;
+    }
+
+    // 3.2 手续费: 流动性部分进入 cash 池, 协议部分进入协议金库
+    let (fee, protocol_fee) = compute_fees(&ctx.accounts.amm, repay_amount)?;
+    if fee > protocol_fee {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.lender_cash_token,
+            &ctx.accounts.pool_account_cash,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.lender.to_account_info(),
+            &[],
+            fee - protocol_fee,
+        )?;
+    }
+    if protocol_fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.lender_cash_token,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.lender.to_account_info(),
+            &[],
+            protocol_fee,
+        )?;
+    }
+    emit!(FeeCharged {
+        pool: ctx.accounts.pool.key(),
+        user: ctx.accounts.lender.key(),
+        fee,
+        protocol_fee,
+    });
+
+    if ctx.accounts.pool.collateral()?.freeze_cash || cash_frozen {
+        freeze_token(
+            &ctx.accounts.token_program,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.lender_cash_token,
+            &ctx.accounts.amm_authority,
+            amm_signer_seeds,
+        )?;
+    }
+
     // 4. 提取本金
-    let pool_authority_seeds = &[
-        ctx.accounts.pool.amm.as_ref(),
-        &ctx.accounts.mint_a.key().to_bytes(),
-        AUTHORITY_SEED,
-        &[ctx.bumps.pool_authority],
-    ];
-    let pool_signer_seeds = &[&pool_authority_seeds[..]];
 
-    token::transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.pool_account_a.to_account_info(),
-                to: ctx.accounts.lender_token_a.to_account_info(),
-                authority: ctx.accounts.pool_authority.to_account_info(),
-            },
-            pool_signer_seeds,
-        ),
+    transfer_tokens(
+        &ctx.accounts.collateral_token_program,
+        &ctx.accounts.pool_account_a,
+        &ctx.accounts.lender_token_a,
+        &ctx.accounts.mint_a,
+        &ctx.accounts.pool_authority.to_account_info(),
+        pool_signer_seeds,
         lender_lending_receipt_amount,
     )?;
 
+    // 5. 更新仓位
+    let pool = &mut ctx.accounts.pool;
+    let state = pool.collateral_mut()?;
+    state.total_scaled_debt = state
+        .total_scaled_debt
+        .checked_sub(scaled_debt)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_a_amount = pool
+        .token_a_amount
+        .checked_sub(lender_lending_receipt_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    pool.token_b_amount = pool
+        .token_b_amount
+        .checked_sub(cash_debt)
+        .ok_or(FallError::ArithmeticOverflow)?;
+
+    let position = &mut ctx.accounts.lender_position;
+    position.collateral_amount = position
+        .collateral_amount
+        .checked_sub(lender_lending_receipt_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.cash_debt = position
+        .cash_debt
+        .checked_sub(cash_debt)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    position.scaled_debt = position
+        .scaled_debt
+        .checked_sub(scaled_debt)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    require!(
+        is_healthy(
+            collateral_value(position.collateral_amount, price)?,
+            debt_from_scaled(position.scaled_debt, ctx.accounts.pool.collateral()?.borrow_index)?,
+            ctx.accounts.pool.collateral()?.min_collateral_ratio,
+        ),
+        FallError::PositionUnhealthy
+    );
+    position.last_update_timestamp = Clock::get()?.unix_timestamp;
+
     Ok(())
 }
 
-#[error_code]
-pub enum RedeemError {
-    #[msg("Calculation error")]
-    CalculationError,
-}
\ No newline at end of file
+pub fn redeem_all(ctx: Context<Redeem>) -> Result<()> {
+    let collateral_amount = ctx.accounts.lender_position.collateral_amount;
+    redeem(ctx, collateral_amount)
+}
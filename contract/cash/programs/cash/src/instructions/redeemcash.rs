@@ -1,72 +1,84 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
-    token::{self, Mint, Token, TokenAccount, Transfer, Burn},
+    token_interface::{self, Burn, Mint, TokenAccount, TokenInterface},
     associated_token::AssociatedToken,
 };
 use crate::{
-    constants::{AUTHORITY_SEED,CASH_TOKEN_SEED,SCASH_TOKEN_SEED,CASH_POOL},
-    state::Pool,
+    constants::{AUTHORITY_SEED,CASH_TOKEN_SEED,SCASH_TOKEN_SEED,CASH_POOL,PROTOCOL_FEE_SEED,PAUSE_WITHDRAWALS},
+    errors::FallError,
+    events::FeeCharged,
+    state::{Amm, Pool, PoolKind},
 };
+use crate::instructions::utils::{cash_for_shares, compute_fees, require_not_paused, transfer_tokens};
 
 
 
 #[derive(Accounts)]
 pub struct RedeemCash<'info> {
     #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+    #[account(
+        mut,
         seeds = [
             cash_pool.amm.as_ref(),
-            cash_pool.mint_a.key().as_ref(),
             CASH_POOL,
         ],
         bump,
+        has_one = amm,
+        constraint = cash_pool.kind() == PoolKind::Cash @ FallError::InvalidPoolKind,
     )]
     pub cash_pool: Box<Account<'info, Pool>>,
 
-    /// CHECK: Read only authority
+    /// CHECK: Read only authority, owns the cash vault and the scash mint
     #[account(
         seeds = [
             cash_pool.amm.as_ref(),
-            cash_pool.mint_a.key().as_ref(),
             AUTHORITY_SEED,
         ],
         bump,
     )]
-    pub pool_authority: AccountInfo<'info>,
+    pub amm_authority: AccountInfo<'info>,
 
     #[account(
         mut,
         associated_token::mint = cash_token_mint,
-        associated_token::authority = pool_authority,
+        associated_token::authority = amm_authority,
+        associated_token::token_program = token_program,
     )]
-    pub pool_account_cash: Box<Account<'info, TokenAccount>>,
+    pub pool_account_cash: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         seeds = [
             cash_pool.amm.as_ref(),
-            cash_pool.mint_a.key().as_ref(),
             CASH_TOKEN_SEED,
         ],
         bump,
         mint::decimals = 6,
-        mint::authority = pool_authority,        // mint authority 是 pool_authority
-        mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::authority = amm_authority,        // mint authority 是 amm_authority
+        mint::freeze_authority = amm_authority, // freeze authority 也是 amm_authority
+        mint::token_program = token_program,
     )]
-    pub cash_token_mint: Box<Account<'info, Mint>>,
+    pub cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         seeds = [
             cash_pool.amm.as_ref(),
-            cash_pool.mint_a.key().as_ref(),
             SCASH_TOKEN_SEED,
         ],
         bump,
         mint::decimals = 6,
-        mint::authority = pool_authority,        // mint authority 是 pool_authority
-        mint::freeze_authority = pool_authority, // freeze authority 也是 pool_authority
+        mint::authority = amm_authority,        // mint authority 是 amm_authority
+        mint::freeze_authority = amm_authority, // freeze authority 也是 amm_authority
+        mint::token_program = token_program,
     )]
-    pub s_cash_token_mint: Box<Account<'info, Mint>>,
+    pub s_cash_token_mint: Box<InterfaceAccount<'info, Mint>>,
 
     pub lender: Signer<'info>,
 
@@ -74,40 +86,75 @@ pub struct RedeemCash<'info> {
         mut,
         associated_token::mint = cash_token_mint,
         associated_token::authority = lender,
+        associated_token::token_program = token_program,
     )]
-    pub lender_cash_token: Box<Account<'info, TokenAccount>>,
+    pub lender_cash_token: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint = s_cash_token_mint,
         associated_token::authority = lender,
+        associated_token::token_program = token_program,
+    )]
+    pub lender_scash_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = cash_token_mint,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = token_program,
     )]
-    pub lender_scash_token: Box<Account<'info, TokenAccount>>,
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(mut)]
     pub payer: Signer<'info>,
 
     // 4. Programs
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 
 }
 
-pub fn redeem_cash(ctx: Context<RedeemCash>) -> Result<()> {
-    // 1. 解码 lending receipt token 数量
-    let lender_lending_receipt_amount = ctx.accounts.lender_scash_token.amount;
+pub fn redeem_cash(ctx: Context<RedeemCash>, lender_lending_receipt_amount: u64) -> Result<()> {
+    require_not_paused(&ctx.accounts.amm, &ctx.accounts.cash_pool, PAUSE_WITHDRAWALS)?;
+
+    // 1. 校验 scash 余额与池子可用 cash
+    require!(lender_lending_receipt_amount > 0, FallError::DepositTooSmall);
+    require!(
+        ctx.accounts.lender_scash_token.amount >= lender_lending_receipt_amount,
+        FallError::InsufficientBalance
+    );
+    let cash_amount = cash_for_shares(
+        lender_lending_receipt_amount,
+        ctx.accounts.pool_account_cash.amount,
+        ctx.accounts.s_cash_token_mint.supply,
+    )?;
+    require!(
+        ctx.accounts.pool_account_cash.amount >= cash_amount,
+        FallError::InsufficientLiquidity
+    );
 
     let authority_seeds = &[
         &ctx.accounts.cash_pool.amm.to_bytes(),
-        &ctx.accounts.cash_pool.mint_a.key().to_bytes(),
         AUTHORITY_SEED,
-        &[ctx.bumps.pool_authority],
+        &[ctx.bumps.amm_authority],
     ];
     let signer_seeds = &[&authority_seeds[..]];
    
     // 2. 解冻、销毁 lending receipt token
-    token::burn(
+    token_interface::burn(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Burn {
@@ -120,25 +167,45 @@ pub fn redeem_cash(ctx: Context<RedeemCash>) -> Result<()> {
         lender_lending_receipt_amount,
     )?;
 
-    // 4. 提取本金
-    token::transfer(
-        CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.pool_account_cash.to_account_info(),
-                to: ctx.accounts.lender_cash_token.to_account_info(),
-                authority: ctx.accounts.pool_authority.to_account_info(),
-            },
+    // 3. 手续费: 流动性部分留在池中, 协议部分进入协议金库
+    let (fee, protocol_fee) = compute_fees(&ctx.accounts.amm, cash_amount)?;
+    if protocol_fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program,
+            &ctx.accounts.pool_account_cash,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.cash_token_mint,
+            &ctx.accounts.amm_authority.to_account_info(),
             signer_seeds,
-        ),
-        lender_lending_receipt_amount,
+            protocol_fee,
+        )?;
+    }
+    emit!(FeeCharged {
+        pool: ctx.accounts.cash_pool.key(),
+        user: ctx.accounts.lender.key(),
+        fee,
+        protocol_fee,
+    });
+
+    // 4. 提取本金
+    transfer_tokens(
+        &ctx.accounts.token_program,
+        &ctx.accounts.pool_account_cash,
+        &ctx.accounts.lender_cash_token,
+        &ctx.accounts.cash_token_mint,
+        &ctx.accounts.amm_authority.to_account_info(),
+        signer_seeds,
+        cash_amount - fee,
     )?;
 
+    // 5. 记录质押中的 cash 总量 (含已计入池子的利息与流动性手续费)
+    ctx.accounts.cash_pool.token_b_amount = ctx
+        .accounts
+        .pool_account_cash
+        .amount
+        .checked_sub(cash_amount)
+        .and_then(|amount| amount.checked_add(fee - protocol_fee))
+        .ok_or(FallError::ArithmeticOverflow)?;
+
     Ok(())
 }
-
-#[error_code]
-pub enum RedeemError {
-    #[msg("Calculation error")]
-    CalculationError,
-}
\ No newline at end of file
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use crate::state::Amm;
+use crate::instructions::utils::validate_pause_authority;
+
+#[derive(Accounts)]
+pub struct SetAmmPause<'info> {
+    #[account(
+        mut,
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    /// Admin or guardian
+    pub authority: Signer<'info>,
+}
+
+
+pub fn set_amm_pause(ctx: Context<SetAmmPause>, pause_flags: u8) -> Result<()> {
+    let amm = &mut ctx.accounts.amm;
+    validate_pause_authority(amm, &ctx.accounts.authority.key(), amm.pause_flags, pause_flags)?;
+    amm.pause_flags = pause_flags;
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+use crate::{
+    constants::AUTHORITY_SEED,
+    errors::FallError,
+    oracle::{collateral_value, get_price},
+    state::{Amm, Pool, PoolKind},
+};
+use crate::instructions::utils::{accrue_interest, validate_rate_model};
+
+#[derive(Accounts)]
+pub struct SetInterestRateModel<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            amm.key().as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            pool.mint_a.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        associated_token::mint = pool.mint_a,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Validated against pool.oracle when reading the price
+    pub oracle: AccountInfo<'info>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+
+pub fn set_interest_rate_model(
+    ctx: Context<SetInterestRateModel>,
+    base_rate: u64,
+    kink: u64,
+    slope_1: u64,
+    slope_2: u64,
+) -> Result<()> {
+    validate_rate_model(base_rate, kink, slope_1, slope_2)?;
+
+    // 先按旧参数结算利息
+    let price = get_price(&ctx.accounts.pool, &ctx.accounts.oracle)?;
+    let collateral = collateral_value(ctx.accounts.pool_account_a.amount, price)?;
+    accrue_interest(ctx.accounts.pool.collateral_mut()?, collateral)?;
+
+    let pool = ctx.accounts.pool.collateral_mut()?;
+    pool.base_rate = base_rate;
+    pool.kink = kink;
+    pool.slope_1 = slope_1;
+    pool.slope_2 = slope_2;
+
+    Ok(())
+}
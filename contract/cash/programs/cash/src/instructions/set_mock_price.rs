@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use crate::{
+    constants::MOCK_PRICE_FEED_SEED,
+    state::{Amm, MockPriceFeed},
+};
+
+#[derive(Accounts)]
+pub struct SetMockPrice<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = MockPriceFeed::LEN,
+        seeds = [
+            amm.key().as_ref(),
+            mint_a.key().as_ref(),
+            MOCK_PRICE_FEED_SEED,
+        ],
+        bump,
+    )]
+    pub mock_price_feed: Box<Account<'info, MockPriceFeed>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+
+pub fn set_mock_price(ctx: Context<SetMockPrice>, price: i64, expo: i32, conf: u64) -> Result<()> {
+    let feed = &mut ctx.accounts.mock_price_feed;
+    feed.amm = ctx.accounts.amm.key();
+    feed.price = price;
+    feed.expo = expo;
+    feed.conf = conf;
+    feed.publish_time = Clock::get()?.unix_timestamp;
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::{
+    errors::FallError,
+    oracle::get_price,
+    state::{Amm, OracleType, Pool, PoolKind},
+};
+
+#[derive(Accounts)]
+pub struct SetOracle<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        mut,
+        seeds = [
+            amm.key().as_ref(),
+            pool.mint_a.key().as_ref(),
+        ],
+        bump,
+        constraint = pool.kind() == PoolKind::Collateral @ FallError::InvalidPoolKind,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// CHECK: Price account, layout validated by reading a price
+    pub oracle: AccountInfo<'info>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+}
+
+
+pub fn set_oracle(
+    ctx: Context<SetOracle>,
+    oracle_type: OracleType,
+    max_price_age: u64,
+    max_confidence: u64,
+) -> Result<()> {
+    let pool = ctx.accounts.pool.collateral_mut()?;
+    pool.oracle = ctx.accounts.oracle.key();
+    pool.oracle_type = oracle_type;
+    pool.max_price_age = max_price_age;
+    pool.max_confidence = max_confidence;
+
+    // 读取一次价格, 校验账户格式与参数
+    get_price(&ctx.accounts.pool, &ctx.accounts.oracle)?;
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use crate::state::{Amm, Pool};
+use crate::instructions::utils::validate_pause_authority;
+
+#[derive(Accounts)]
+pub struct SetPoolPause<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    /// 借贷池或 cash 池
+    #[account(
+        mut,
+        has_one = amm,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// Admin or guardian
+    pub authority: Signer<'info>,
+}
+
+
+pub fn set_pool_pause(ctx: Context<SetPoolPause>, pause_flags: u8) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    validate_pause_authority(
+        &ctx.accounts.amm,
+        &ctx.accounts.authority.key(),
+        pool.pause_flags,
+        pause_flags,
+    )?;
+    pool.pause_flags = pause_flags;
+
+    Ok(())
+}
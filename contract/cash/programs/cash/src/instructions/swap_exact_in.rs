@@ -0,0 +1,213 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+use crate::{
+    constants::{AUTHORITY_SEED, PAUSE_DEPOSITS, PAUSE_WITHDRAWALS, PROTOCOL_FEE_SEED, SWAP_POOL_SEED},
+    curve::{check_constant_product, check_stable_swap, constant_product_swap, stable_swap},
+    errors::FallError,
+    events::FeeCharged,
+    state::{Amm, CurveType, SwapPool},
+};
+use crate::instructions::utils::{compute_fees, require_amm_not_paused, transfer_tokens};
+
+#[derive(Accounts)]
+pub struct SwapExactIn<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        seeds = [
+            swap_pool.amm.as_ref(),
+            swap_pool.mint_a.key().as_ref(),
+            swap_pool.mint_b.key().as_ref(),
+            SWAP_POOL_SEED,
+        ],
+        bump,
+        has_one = amm,
+    )]
+    pub swap_pool: Box<Account<'info, SwapPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            swap_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub swap_pool_authority: AccountInfo<'info>,
+
+    /// 输入、输出 mint 必须分别是池子的两种 token
+    #[account(
+        mint::token_program = token_program_in,
+        constraint = (input_mint.key() == swap_pool.mint_a && output_mint.key() == swap_pool.mint_b)
+            || (input_mint.key() == swap_pool.mint_b && output_mint.key() == swap_pool.mint_a)
+            @ FallError::InvalidMint,
+    )]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mint::token_program = token_program_out,
+    )]
+    pub output_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = input_mint,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_in,
+    )]
+    pub pool_account_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = output_mint,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_out,
+    )]
+    pub pool_account_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub trader: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = input_mint,
+        token::token_program = token_program_in,
+    )]
+    pub trader_input: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = output_mint,
+        token::token_program = token_program_out,
+    )]
+    pub trader_output: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            PROTOCOL_FEE_SEED,
+        ],
+        bump,
+    )]
+    pub protocol_fee_authority: AccountInfo<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = input_mint,
+        associated_token::authority = protocol_fee_authority,
+        associated_token::token_program = token_program_in,
+    )]
+    pub protocol_fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program_in: Interface<'info, TokenInterface>,
+    pub token_program_out: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn swap_exact_in(ctx: Context<SwapExactIn>, amount_in: u64, min_amount_out: u64) -> Result<()> {
+    require_amm_not_paused(&ctx.accounts.amm, PAUSE_DEPOSITS | PAUSE_WITHDRAWALS)?;
+    require!(amount_in > 0, FallError::DepositTooSmall);
+
+    let reserve_in = ctx.accounts.pool_account_in.amount;
+    let reserve_out = ctx.accounts.pool_account_out.amount;
+
+    // 1. 手续费: 流动性部分留在池中, 协议部分进入协议金库
+    let (fee, protocol_fee) = compute_fees(&ctx.accounts.amm, amount_in)?;
+    transfer_tokens(
+        &ctx.accounts.token_program_in,
+        &ctx.accounts.trader_input,
+        &ctx.accounts.pool_account_in,
+        &ctx.accounts.input_mint,
+        &ctx.accounts.trader.to_account_info(),
+        &[],
+        amount_in - protocol_fee,
+    )?;
+    if protocol_fee > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program_in,
+            &ctx.accounts.trader_input,
+            &ctx.accounts.protocol_fee_vault,
+            &ctx.accounts.input_mint,
+            &ctx.accounts.trader.to_account_info(),
+            &[],
+            protocol_fee,
+        )?;
+    }
+
+    // 2. 以池子实际收到的数量扣除流动性手续费后计算输出
+    ctx.accounts.pool_account_in.reload()?;
+    let received = ctx.accounts.pool_account_in.amount.saturating_sub(reserve_in);
+    let swap_amount = received.saturating_sub(fee - protocol_fee);
+    let new_reserve_in = ctx.accounts.pool_account_in.amount;
+    let amount_out = match ctx.accounts.swap_pool.curve_type {
+        CurveType::ConstantProduct => {
+            let amount_out = constant_product_swap(swap_amount, reserve_in, reserve_out)?;
+            require!(amount_out < reserve_out, FallError::InsufficientLiquidity);
+            check_constant_product(reserve_in, reserve_out, new_reserve_in, reserve_out - amount_out)?;
+            amount_out
+        }
+        CurveType::StableSwap => {
+            let amp = ctx.accounts.swap_pool.current_amp(Clock::get()?.unix_timestamp);
+            let (decimals_in, decimals_out) =
+                (ctx.accounts.input_mint.decimals, ctx.accounts.output_mint.decimals);
+            let amount_out =
+                stable_swap(swap_amount, reserve_in, reserve_out, decimals_in, decimals_out, amp)?;
+            require!(amount_out < reserve_out, FallError::InsufficientLiquidity);
+            check_stable_swap(
+                amp,
+                reserve_in,
+                reserve_out,
+                new_reserve_in,
+                reserve_out - amount_out,
+                decimals_in,
+                decimals_out,
+            )?;
+            amount_out
+        }
+    };
+    require!(
+        amount_out > 0 && amount_out >= min_amount_out,
+        FallError::OutputTooSmall
+    );
+
+    // 3. 转出
+    let swap_pool_key = ctx.accounts.swap_pool.key();
+    let authority_seeds = &[
+        swap_pool_key.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.swap_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    transfer_tokens(
+        &ctx.accounts.token_program_out,
+        &ctx.accounts.pool_account_out,
+        &ctx.accounts.trader_output,
+        &ctx.accounts.output_mint,
+        &ctx.accounts.swap_pool_authority.to_account_info(),
+        signer_seeds,
+        amount_out,
+    )?;
+
+    emit!(FeeCharged {
+        pool: ctx.accounts.swap_pool.key(),
+        user: ctx.accounts.trader.key(),
+        fee,
+        protocol_fee,
+    });
+
+    Ok(())
+}
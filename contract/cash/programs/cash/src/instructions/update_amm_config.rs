@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::Amm;
+use crate::instructions::utils::validate_fees;
+
+#[derive(Accounts)]
+pub struct UpdateAmmConfig<'info> {
+    #[account(
+        mut,
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+}
+
+
+pub fn update_amm_config(
+    ctx: Context<UpdateAmmConfig>,
+    liquidity_fee: u16,
+    protocol_fee_percentage: u16,
+) -> Result<()> {
+    validate_fees(liquidity_fee, protocol_fee_percentage)?;
+
+    let amm = &mut ctx.accounts.amm;
+    amm.liquidity_fee = liquidity_fee;
+    amm.protocol_fee_percentage = protocol_fee_percentage;
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+use crate::{
+    constants::AUTHORITY_SEED,
+    errors::FallError,
+    oracle::{collateral_value, get_price},
+    state::{Amm, Pool, PoolState},
+};
+use crate::instructions::utils::{
+    accrue_interest, validate_collateral_ratio, validate_flash_loan_fee, validate_liquidation_params,
+    validate_psm_fee,
+};
+
+/// 只更新传入 Some 的字段
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct PoolConfigParams {
+    pub collateral_ratio: Option<u64>,
+    pub min_collateral_ratio: Option<u64>,
+    pub liquidation_bonus: Option<u64>,
+    pub close_factor: Option<u64>,
+    pub min_deposit: Option<u64>,
+    pub max_position_size: Option<u64>,
+    pub deposit_cap: Option<u64>,
+    pub freeze_cash: Option<bool>,
+    pub debt_ceiling: Option<u64>,
+    pub flash_loan_fee: Option<u64>,
+    pub flash_mint_cap: Option<u64>,
+    pub flash_mint_fee: Option<u64>,
+    pub psm_mint_fee: Option<u64>,
+    pub psm_redeem_fee: Option<u64>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePoolConfig<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    /// 借贷池、cash 池或 PSM 池
+    #[account(
+        mut,
+        has_one = amm,
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    /// 以下账户仅在修改抵押率时需要, 用于先按旧参数结算利息
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            amm.key().as_ref(),
+            pool.mint_a.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub pool_authority: Option<AccountInfo<'info>>,
+
+    #[account(
+        associated_token::mint = pool.mint_a,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = collateral_token_program,
+    )]
+    pub pool_account_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Validated against pool.oracle when reading the price
+    pub oracle: Option<AccountInfo<'info>>,
+
+    #[account(
+        constraint = admin.key() == amm.admin
+    )]
+    pub admin: Signer<'info>,
+
+    pub collateral_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+
+pub fn update_pool_config(ctx: Context<UpdatePoolConfig>, params: PoolConfigParams) -> Result<()> {
+    // 每个参数只存在于对应类型的池子中, 类型不符时返回 InvalidPoolKind
+    if params.collateral_ratio.is_some() || params.min_collateral_ratio.is_some() {
+        // 先按旧抵押率结算利息
+        let (Some(pool_account_a), Some(oracle)) =
+            (&ctx.accounts.pool_account_a, &ctx.accounts.oracle)
+        else {
+            return err!(FallError::MissingAccrualAccounts);
+        };
+        let price = get_price(&ctx.accounts.pool, oracle)?;
+        let collateral = collateral_value(pool_account_a.amount, price)?;
+        let pool = ctx.accounts.pool.collateral_mut()?;
+        accrue_interest(pool, collateral)?;
+
+        let collateral_ratio = params.collateral_ratio.unwrap_or(pool.collateral_ratio);
+        let min_collateral_ratio = params.min_collateral_ratio.unwrap_or(pool.min_collateral_ratio);
+        validate_collateral_ratio(collateral_ratio, min_collateral_ratio)?;
+        pool.collateral_ratio = collateral_ratio;
+        pool.min_collateral_ratio = min_collateral_ratio;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    if params.liquidation_bonus.is_some() || params.close_factor.is_some() {
+        let collateral = pool.collateral_mut()?;
+        let liquidation_bonus = params.liquidation_bonus.unwrap_or(collateral.liquidation_bonus);
+        let close_factor = params.close_factor.unwrap_or(collateral.close_factor);
+        validate_liquidation_params(liquidation_bonus, close_factor)?;
+        collateral.liquidation_bonus = liquidation_bonus;
+        collateral.close_factor = close_factor;
+    }
+
+    // 存款限额只存在于有存款仓位的抵押池与 cash 池, PSM 池以 debt ceiling 限额
+    if let Some(min_deposit) = params.min_deposit {
+        pool.deposit_limits_mut()?.min_deposit = min_deposit;
+    }
+    if let Some(max_position_size) = params.max_position_size {
+        pool.deposit_limits_mut()?.max_position_size = max_position_size;
+    }
+    if let Some(deposit_cap) = params.deposit_cap {
+        pool.deposit_limits_mut()?.deposit_cap = deposit_cap;
+    }
+    if let Some(freeze_cash) = params.freeze_cash {
+        pool.collateral_mut()?.freeze_cash = freeze_cash;
+    }
+    // debt ceiling 适用于抵押池与 PSM 池
+    if let Some(debt_ceiling) = params.debt_ceiling {
+        match &mut pool.state {
+            PoolState::Collateral(collateral) => collateral.debt_ceiling = debt_ceiling,
+            PoolState::Psm(psm) => psm.debt_ceiling = debt_ceiling,
+            PoolState::Cash(_) => return err!(FallError::InvalidPoolKind),
+        }
+    }
+    if let Some(flash_loan_fee) = params.flash_loan_fee {
+        validate_flash_loan_fee(flash_loan_fee)?;
+        pool.collateral_mut()?.flash_loan_fee = flash_loan_fee;
+    }
+    if let Some(flash_mint_cap) = params.flash_mint_cap {
+        pool.collateral_mut()?.flash_mint_cap = flash_mint_cap;
+    }
+    if let Some(flash_mint_fee) = params.flash_mint_fee {
+        validate_flash_loan_fee(flash_mint_fee)?;
+        pool.collateral_mut()?.flash_mint_fee = flash_mint_fee;
+    }
+    if let Some(psm_mint_fee) = params.psm_mint_fee {
+        validate_psm_fee(psm_mint_fee)?;
+        pool.psm_mut()?.psm_mint_fee = psm_mint_fee;
+    }
+    if let Some(psm_redeem_fee) = params.psm_redeem_fee {
+        validate_psm_fee(psm_redeem_fee)?;
+        pool.psm_mut()?.psm_redeem_fee = psm_redeem_fee;
+    }
+
+    Ok(())
+}
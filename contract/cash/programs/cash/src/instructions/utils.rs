@@ -1,17 +1,52 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, MintTo};
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::token_interface::{
+    self, spl_token_2022::{
+        self,
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    }, FreezeAccount, Mint, MintTo, ThawAccount,
+    TokenAccount, TokenInterface, TransferChecked,
+};
+use crate::{
+    constants::{
+        INDEX_PRECISION, MAX_AMP, MAX_BORROW_RATE_BPS, MAX_FLASH_LOAN_FEE, MAX_LIQUIDITY_FEE,
+        MAX_PSM_FEE, MIN_AMP, PAUSE_ALL, RATIO_PRECISION, SECONDS_PER_YEAR,
+    },
+    errors::FallError,
+    state::{Amm, CollateralPool, DepositLimits, Pool},
+};
 
 #[inline(never)]
 pub fn mint_and_freeze_token<'info>(
-    token_program: &Program<'info, Token>,
-    mint: &Box<Account<'info, Mint>>,
-    recipient: &Box<Account<'info, TokenAccount>>,
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    recipient: &InterfaceAccount<'info, TokenAccount>,
     authority: &AccountInfo<'info>,
     signer_seeds: &[&[&[u8]]],
     amount: u64,
 ) -> Result<()> {
-    // 1. Mint tokens
-    token::mint_to(
+    // 1. Thaw the account frozen by a previous mint
+    thaw_token(token_program, mint, recipient, authority, signer_seeds)?;
+
+    // 2. Mint tokens
+    mint_token(token_program, mint, recipient, authority, signer_seeds, amount)?;
+
+    // 3. Freeze the account
+    freeze_token(token_program, mint, recipient, authority, signer_seeds)
+}
+
+#[inline(never)]
+pub fn mint_token<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    recipient: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    token_interface::mint_to(
         CpiContext::new_with_signer(
             token_program.to_account_info(),
             MintTo {
@@ -22,12 +57,545 @@ pub fn mint_and_freeze_token<'info>(
             signer_seeds,
         ),
         amount,
+    )
+}
+
+/// Thaw the account if it is frozen; authority must be the mint's freeze authority
+pub fn thaw_token<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    account: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if !account.is_frozen() {
+        return Ok(());
+    }
+    token_interface::thaw_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        ThawAccount {
+            account: account.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: authority.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// Freeze the account; authority must be the mint's freeze authority
+pub fn freeze_token<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    account: &InterfaceAccount<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    token_interface::freeze_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        FreezeAccount {
+            account: account.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: authority.to_account_info(),
+        },
+        signer_seeds,
+    ))
+}
+
+/// Transfer with the mint's decimals so the same call works for Token and Token-2022
+pub fn transfer_tokens<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Token-2022 extensions that let a third party move, block or hide pool collateral
+const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 5] = [
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+    ExtensionType::NonTransferable,
+    ExtensionType::DefaultAccountState,
+    ExtensionType::ConfidentialTransferMint,
+];
+
+/// Reject collateral mints carrying extensions the pool can't safely custody
+pub fn validate_collateral_mint(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == anchor_spl::token::ID {
+        return Ok(());
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let extensions = state.get_extension_types()?;
+    require!(
+        !extensions.iter().any(|e| UNSUPPORTED_MINT_EXTENSIONS.contains(e)),
+        FallError::UnsupportedMintExtension
+    );
+    Ok(())
+}
+
+/// 闪电贷要求同一交易内足额归还, Token-2022 转账手续费会使到账数量不足
+pub fn reject_transfer_fee_mint(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner == anchor_spl::token::ID {
+        return Ok(());
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    require!(
+        !state.get_extension_types()?.contains(&ExtensionType::TransferFeeConfig),
+        FallError::UnsupportedMintExtension
+    );
+    Ok(())
+}
+
+/// 闪电贷还款指令账户列表中 pool 的位置 (amm 之后)
+const FLASH_REPAY_POOL_INDEX: usize = 1;
+
+/// 当前指令必须是顶层调用, 且之后存在同一 pool 的还款指令
+pub fn require_flash_repay(
+    instructions: &AccountInfo,
+    repay_discriminator: &[u8],
+    pool: Pubkey,
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)? as usize;
+    let current = load_instruction_at_checked(current_index, instructions)?;
+    require_keys_eq!(current.program_id, crate::ID, FallError::FlashRepayMissing);
+
+    let mut index = current_index + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID
+            && ix.data.starts_with(repay_discriminator)
+            && ix
+                .accounts
+                .get(FLASH_REPAY_POOL_INDEX)
+                .is_some_and(|meta| meta.pubkey == pool)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+
+    err!(FallError::FlashRepayMissing)
+}
+
+/// amount * numerator / denominator, rounded down
+pub fn mul_div(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    require!(denominator > 0, FallError::CalculationError);
+    let result = (amount as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(FallError::ArithmeticOverflow)?
+        / denominator as u128;
+    u64::try_from(result).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// amount * numerator / denominator, rounded up
+pub fn mul_div_ceil(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    let result = mul_div_ceil_u128(amount as u128, numerator as u128, denominator as u128)?;
+    u64::try_from(result).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// amount * numerator / denominator, rounded up
+pub fn mul_div_ceil_u128(amount: u128, numerator: u128, denominator: u128) -> Result<u128> {
+    require!(denominator > 0, FallError::CalculationError);
+    let product = amount
+        .checked_mul(numerator)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    Ok(product
+        .checked_add(denominator - 1)
+        .ok_or(FallError::ArithmeticOverflow)?
+        / denominator)
+}
+
+/// 校验抵押率参数: 100% <= min_collateral_ratio <= collateral_ratio
+pub fn validate_collateral_ratio(collateral_ratio: u64, min_collateral_ratio: u64) -> Result<()> {
+    require!(
+        min_collateral_ratio >= RATIO_PRECISION && collateral_ratio >= min_collateral_ratio,
+        FallError::InvalidCollateralRatio
+    );
+    Ok(())
+}
+
+/// collateral / debt >= ratio
+pub fn is_healthy(collateral: u64, debt: u64, ratio: u64) -> bool {
+    (collateral as u128) * (RATIO_PRECISION as u128) >= (debt as u128) * (ratio as u128)
+}
+
+/// 校验利率曲线参数: 0 < kink < 100%, 最高利率 base + slope_1 + slope_2 <= MAX_BORROW_RATE_BPS
+pub fn validate_rate_model(base_rate: u64, kink: u64, slope_1: u64, slope_2: u64) -> Result<()> {
+    require!(
+        kink > 0 && kink < RATIO_PRECISION,
+        FallError::InvalidRateModel
+    );
+    let max_rate = base_rate
+        .checked_add(slope_1)
+        .and_then(|rate| rate.checked_add(slope_2))
+        .ok_or(FallError::InvalidRateModel)?;
+    require!(max_rate <= MAX_BORROW_RATE_BPS, FallError::InvalidRateModel);
+    Ok(())
+}
+
+/// cash 债务 -> 归一化债务, rounded up
+pub fn scaled_from_debt(debt: u64, borrow_index: u128) -> Result<u128> {
+    mul_div_ceil_u128(debt as u128, INDEX_PRECISION, borrow_index)
+}
+
+/// 归一化债务 -> cash 债务, rounded up
+pub fn debt_from_scaled(scaled_debt: u128, borrow_index: u128) -> Result<u64> {
+    let debt = mul_div_ceil_u128(scaled_debt, borrow_index, INDEX_PRECISION)?;
+    u64::try_from(debt).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// 利用率 = 总债务 / 抵押物可铸造的 cash 上限, capped at 100%
+pub fn utilization(pool: &CollateralPool, collateral: u64) -> Result<u64> {
+    let total_debt = debt_from_scaled(pool.total_scaled_debt, pool.borrow_index)?;
+    let capacity = mul_div(collateral, RATIO_PRECISION, pool.collateral_ratio)?;
+    if capacity == 0 {
+        return Ok(if total_debt == 0 { 0 } else { RATIO_PRECISION });
+    }
+    Ok(mul_div(total_debt, RATIO_PRECISION, capacity)?.min(RATIO_PRECISION))
+}
+
+/// 年化借款利率 (bps)
+pub fn borrow_rate(pool: &CollateralPool, utilization: u64) -> Result<u64> {
+    let slope_rate = if utilization <= pool.kink {
+        mul_div(pool.slope_1, utilization, pool.kink)?
+    } else {
+        let excess = mul_div(
+            pool.slope_2,
+            utilization - pool.kink,
+            RATIO_PRECISION - pool.kink,
+        )?;
+        pool.slope_1
+            .checked_add(excess)
+            .ok_or(FallError::ArithmeticOverflow)?
+    };
+    pool.base_rate
+        .checked_add(slope_rate)
+        .ok_or(error!(FallError::ArithmeticOverflow))
+}
+
+/// 按经过的秒数累计 borrow_index
+pub fn accrue_interest(pool: &mut CollateralPool, collateral: u64) -> Result<()> {
+    // 闪电贷期间 pool_account_a 余额被临时借出, 不能据此计息
+    require!(pool.flash_loan_amount == 0, FallError::FlashLoanActive);
+
+    let now = Clock::get()?.unix_timestamp;
+    let elapsed = now.saturating_sub(pool.last_accrual_timestamp);
+    if elapsed <= 0 {
+        return Ok(());
+    }
+    pool.last_accrual_timestamp = now;
+    if pool.total_scaled_debt == 0 {
+        return Ok(());
+    }
+
+    let rate = borrow_rate(pool, utilization(pool, collateral)?)?;
+    let interest_factor = mul_div_ceil_u128(
+        (rate as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(FallError::ArithmeticOverflow)?,
+        INDEX_PRECISION,
+        (RATIO_PRECISION as u128) * (SECONDS_PER_YEAR as u128),
     )?;
+    let index_delta = mul_div_ceil_u128(pool.borrow_index, interest_factor, INDEX_PRECISION)?;
+    pool.borrow_index = pool
+        .borrow_index
+        .checked_add(index_delta)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// cash -> sCash 份额, rounded down. 虚拟份额 +1 防止首个存款人操纵汇率
+pub fn shares_for_cash(cash: u64, total_cash: u64, total_shares: u64) -> Result<u64> {
+    mul_div(
+        cash,
+        total_shares.checked_add(1).ok_or(FallError::ArithmeticOverflow)?,
+        total_cash.checked_add(1).ok_or(FallError::ArithmeticOverflow)?,
+    )
+}
+
+/// sCash 份额 -> cash, rounded down
+pub fn cash_for_shares(shares: u64, total_cash: u64, total_shares: u64) -> Result<u64> {
+    mul_div(
+        shares,
+        total_cash.checked_add(1).ok_or(FallError::ArithmeticOverflow)?,
+        total_shares.checked_add(1).ok_or(FallError::ArithmeticOverflow)?,
+    )
+}
+
+/// 校验清算参数: 0 < close_factor <= 100%, bonus < 100%
+pub fn validate_liquidation_params(liquidation_bonus: u64, close_factor: u64) -> Result<()> {
+    require!(
+        liquidation_bonus < RATIO_PRECISION && close_factor > 0 && close_factor <= RATIO_PRECISION,
+        FallError::InvalidLiquidationParams
+    );
+    Ok(())
+}
+
+/// 校验闪电贷手续费: <= 10%
+pub fn validate_flash_loan_fee(flash_loan_fee: u64) -> Result<()> {
+    require!(flash_loan_fee <= MAX_FLASH_LOAN_FEE, FallError::InvalidFlashLoanFee);
+    Ok(())
+}
+
+/// 校验 PSM 手续费: <= 5%
+pub fn validate_psm_fee(psm_fee: u64) -> Result<()> {
+    require!(psm_fee <= MAX_PSM_FEE, FallError::InvalidFee);
+    Ok(())
+}
+
+/// 校验 StableSwap 放大系数: MIN_AMP <= amp <= MAX_AMP
+pub fn validate_amp(amp: u64) -> Result<()> {
+    require!((MIN_AMP..=MAX_AMP).contains(&amp), FallError::InvalidAmp);
+    Ok(())
+}
+
+/// 校验手续费参数: liquidity_fee <= 10%, protocol_fee_percentage <= 100%
+pub fn validate_fees(liquidity_fee: u16, protocol_fee_percentage: u16) -> Result<()> {
+    require!(
+        liquidity_fee <= MAX_LIQUIDITY_FEE && protocol_fee_percentage as u64 <= RATIO_PRECISION,
+        FallError::InvalidFee
+    );
+    Ok(())
+}
+
+/// 按 Amm 费率计算手续费, 返回 (总手续费, 其中协议手续费)
+pub fn compute_fees(amm: &Amm, amount: u64) -> Result<(u64, u64)> {
+    let fee = mul_div_ceil(amount, amm.liquidity_fee as u64, RATIO_PRECISION)?;
+    let protocol_fee = mul_div(fee, amm.protocol_fee_percentage as u64, RATIO_PRECISION)?;
+    Ok((fee, protocol_fee))
+}
+
+/// 全局设置了 flags 中的标志位即拒绝, 用于没有池子暂停位的 swap 池
+pub fn require_amm_not_paused(amm: &Amm, flags: u8) -> Result<()> {
+    require!(amm.pause_flags & flags == 0, FallError::Paused);
+    Ok(())
+}
+
+/// 全局或池子任一设置了 flags 中的标志位即拒绝
+pub fn require_not_paused(amm: &Amm, pool: &Pool, flags: u8) -> Result<()> {
+    require!(
+        (amm.pause_flags | pool.pause_flags) & flags == 0,
+        FallError::Paused
+    );
+    Ok(())
+}
+
+/// admin 可任意设置; guardian 只能增加暂停标志位
+pub fn validate_pause_authority(amm: &Amm, signer: &Pubkey, current: u8, new: u8) -> Result<()> {
+    require!(new & !PAUSE_ALL == 0, FallError::InvalidPauseAuthority);
+    let is_admin = *signer == amm.admin;
+    let is_guardian = amm.guardian != Pubkey::default() && *signer == amm.guardian;
+    require!(
+        is_admin || (is_guardian && new & current == current),
+        FallError::InvalidPauseAuthority
+    );
+    Ok(())
+}
+
+/// 校验铸造后池子的未偿还 cash 不超过 debt ceiling (0 表示不限制)
+pub fn check_debt_ceiling(pool: &CollateralPool, mint_amount: u64) -> Result<()> {
+    if pool.debt_ceiling == 0 {
+        return Ok(());
+    }
+    let outstanding = debt_from_scaled(pool.total_scaled_debt, pool.borrow_index)?
+        .checked_add(mint_amount)
+        .ok_or(FallError::ArithmeticOverflow)?;
+    require!(outstanding <= pool.debt_ceiling, FallError::DebtCeilingExceeded);
+    Ok(())
+}
+
+/// 校验存款额度: 最小存款、单仓位上限、池子总上限 (0 表示不限制)
+pub fn check_deposit_limits(
+    limits: &DepositLimits,
+    amount: u64,
+    position_size: u64,
+    pool_size: u64,
+) -> Result<()> {
+    require!(
+        amount > 0 && amount >= limits.min_deposit,
+        FallError::DepositTooSmall
+    );
+    require!(
+        limits.max_position_size == 0 || position_size <= limits.max_position_size,
+        FallError::PositionSizeExceeded
+    );
+    require!(
+        limits.deposit_cap == 0 || pool_size <= limits.deposit_cap,
+        FallError::DepositCapExceeded
+    );
     Ok(())
 }
 
-#[error_code]
-pub enum UtilsError {
-    #[msg("Calculation error")]
-    CalculationError,
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::set_now;
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    /// borrow_index = 1.0 时 scaled debt 与 cash 债务相等
+    fn pool(debt: u128, collateral_ratio: u64) -> CollateralPool {
+        CollateralPool {
+            collateral_ratio,
+            borrow_index: INDEX_PRECISION,
+            total_scaled_debt: debt,
+            last_accrual_timestamp: NOW,
+            base_rate: 200,
+            kink: 8000,
+            slope_1: 1000,
+            slope_2: 5000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn utilization_is_debt_over_mint_capacity() {
+        // 抵押物价值 1_500_000, 抵押率 150%, 可铸造 1_000_000
+        assert_eq!(utilization(&pool(500_000, 15_000), 1_500_000).unwrap(), 5000);
+        assert_eq!(utilization(&pool(0, 15_000), 1_500_000).unwrap(), 0);
+        // 抵押物贬值后超过 100% 的利用率按 100% 计
+        assert_eq!(utilization(&pool(2_000_000, 15_000), 1_500_000).unwrap(), RATIO_PRECISION);
+        assert_eq!(utilization(&pool(1, 15_000), 0).unwrap(), RATIO_PRECISION);
+        assert_eq!(utilization(&pool(0, 15_000), 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn borrow_rate_steepens_after_kink() {
+        let pool = pool(0, 10_000);
+        assert_eq!(borrow_rate(&pool, 0).unwrap(), 200);
+        // 拐点前: 200 + 1000 * 4000 / 8000
+        assert_eq!(borrow_rate(&pool, 4000).unwrap(), 700);
+        assert_eq!(borrow_rate(&pool, 8000).unwrap(), 1200);
+        // 拐点后: 200 + 1000 + 5000 * 1000 / 2000
+        assert_eq!(borrow_rate(&pool, 9000).unwrap(), 3700);
+        assert_eq!(borrow_rate(&pool, RATIO_PRECISION).unwrap(), 6200);
+    }
+
+    #[test]
+    fn accrue_interest_skips_zero_elapsed() {
+        set_now(NOW);
+        let mut pool = pool(400_000, 10_000);
+        accrue_interest(&mut pool, 1_000_000).unwrap();
+        assert_eq!(pool.borrow_index, INDEX_PRECISION);
+        assert_eq!(pool.last_accrual_timestamp, NOW);
+    }
+
+    #[test]
+    fn accrue_interest_rounds_index_up() {
+        // 利用率 40%, 年化 7%: 1 秒的利息因子 700e12 / (1e4 * 31_536_000) = 2219.7, 向上取整
+        set_now(NOW + 1);
+        let mut pool = pool(400_000, 10_000);
+        accrue_interest(&mut pool, 1_000_000).unwrap();
+        assert_eq!(pool.borrow_index, INDEX_PRECISION + 2220);
+        assert_eq!(pool.last_accrual_timestamp, NOW + 1);
+    }
+
+    #[test]
+    fn accrue_interest_over_one_year() {
+        // 年化 7% 经过一整年单次结算, 指数恰好为 1.07
+        set_now(NOW + SECONDS_PER_YEAR as i64);
+        let mut pool = pool(400_000, 10_000);
+        accrue_interest(&mut pool, 1_000_000).unwrap();
+        assert_eq!(pool.borrow_index, INDEX_PRECISION * 107 / 100);
+    }
+
+    #[test]
+    fn accrue_interest_without_debt_only_moves_timestamp() {
+        set_now(NOW + 3600);
+        let mut pool = pool(0, 10_000);
+        accrue_interest(&mut pool, 1_000_000).unwrap();
+        assert_eq!(pool.borrow_index, INDEX_PRECISION);
+        assert_eq!(pool.last_accrual_timestamp, NOW + 3600);
+    }
+
+    #[test]
+    fn accrue_interest_rejects_active_flash_loan() {
+        set_now(NOW + 1);
+        let mut pool = CollateralPool {
+            flash_loan_amount: 1,
+            ..pool(400_000, 10_000)
+        };
+        assert_eq!(
+            accrue_interest(&mut pool, 1_000_000).unwrap_err(),
+            FallError::FlashLoanActive.into()
+        );
+    }
+
+    #[test]
+    fn first_deposit_mints_shares_one_to_one() {
+        assert_eq!(shares_for_cash(1_000_000, 0, 0).unwrap(), 1_000_000);
+        assert_eq!(cash_for_shares(1_000_000, 1_000_000, 1_000_000).unwrap(), 1_000_000);
+    }
+
+    #[test]
+    fn donation_does_not_profit_the_first_depositor() {
+        // 攻击者存入 1 得到 1 份, 再直接向金库转入 1_000_000 抬高汇率
+        let attacker_shares = shares_for_cash(1, 0, 0).unwrap();
+        assert_eq!(attacker_shares, 1);
+        let total_cash = 1 + 1_000_000;
+
+        // 受害者存入 1_000_000, 份额向下取整
+        let victim_shares = shares_for_cash(1_000_000, total_cash, attacker_shares).unwrap();
+        assert_eq!(victim_shares, 1);
+        let total_cash = total_cash + 1_000_000;
+        let total_shares = attacker_shares + victim_shares;
+
+        // 虚拟份额分走一部分捐赠, 攻击者取回的少于投入
+        let attacker_cash = cash_for_shares(attacker_shares, total_cash, total_shares).unwrap();
+        assert_eq!(attacker_cash, 666_667);
+        assert!(attacker_cash < 1 + 1_000_000);
+    }
+
+    #[test]
+    fn share_round_trip_rounds_against_the_user() {
+        // 1 cash 按 0.9 份计算, 铸造向下取整
+        assert_eq!(shares_for_cash(1, 1_000_000, 900_000).unwrap(), 0);
+        assert_eq!(shares_for_cash(11, 1_000_000, 900_000).unwrap(), 9);
+        // 赎回同样向下取整, 11 cash 存入后只能取回 10
+        assert_eq!(cash_for_shares(9, 1_000_011, 900_009).unwrap(), 10);
+        assert_eq!(cash_for_shares(1, 1_000_000, 900_000).unwrap(), 1);
+
+        for (cash, total_cash, total_shares) in [
+            (10, 1_000_000, 900_000),
+            (1_234_567, 3_000_001, 2_999_999),
+            (7, 5, 3),
+            (999_999, 1, 1_000_000),
+        ] {
+            let shares = shares_for_cash(cash, total_cash, total_shares).unwrap();
+            let redeemed =
+                cash_for_shares(shares, total_cash + cash, total_shares + shares).unwrap();
+            assert!(redeemed <= cash, "{cash} -> {shares} -> {redeemed}");
+        }
+    }
+
+    #[test]
+    fn validate_rate_model_bounds_max_rate() {
+        assert!(validate_rate_model(0, 8000, 0, MAX_BORROW_RATE_BPS).is_ok());
+        assert!(validate_rate_model(1, 8000, 0, MAX_BORROW_RATE_BPS).is_err());
+        assert!(validate_rate_model(u64::MAX, 8000, 1, 0).is_err());
+        assert!(validate_rate_model(0, 0, 0, 0).is_err());
+        assert!(validate_rate_model(0, RATIO_PRECISION, 0, 0).is_err());
+    }
 }
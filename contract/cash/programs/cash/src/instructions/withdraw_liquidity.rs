@@ -0,0 +1,180 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn, Mint, TokenAccount, TokenInterface};
+use crate::{
+    constants::{AUTHORITY_SEED, LP_TOKEN_SEED, PAUSE_WITHDRAWALS, SWAP_POOL_SEED},
+    curve::withdraw_amounts,
+    errors::FallError,
+    state::{Amm, SwapPool},
+};
+use crate::instructions::utils::{require_amm_not_paused, transfer_tokens};
+
+#[derive(Accounts)]
+pub struct WithdrawLiquidity<'info> {
+    #[account(
+        seeds = [
+            amm.id.as_ref()
+        ],
+        bump,
+    )]
+    pub amm: Box<Account<'info, Amm>>,
+
+    #[account(
+        seeds = [
+            swap_pool.amm.as_ref(),
+            swap_pool.mint_a.key().as_ref(),
+            swap_pool.mint_b.key().as_ref(),
+            SWAP_POOL_SEED,
+        ],
+        bump,
+        has_one = amm,
+    )]
+    pub swap_pool: Box<Account<'info, SwapPool>>,
+
+    /// CHECK: Read only authority
+    #[account(
+        seeds = [
+            swap_pool.key().as_ref(),
+            AUTHORITY_SEED,
+        ],
+        bump,
+    )]
+    pub swap_pool_authority: AccountInfo<'info>,
+
+    #[account(
+        address = swap_pool.mint_a,
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        address = swap_pool.mint_b,
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [
+            swap_pool.key().as_ref(),
+            LP_TOKEN_SEED,
+        ],
+        bump,
+        mint::authority = swap_pool_authority,
+        mint::token_program = token_program,
+    )]
+    pub lp_token_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_a,
+    )]
+    pub pool_account_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = swap_pool_authority,
+        associated_token::token_program = token_program_b,
+    )]
+    pub pool_account_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::token_program = token_program_a,
+    )]
+    pub provider_token_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b,
+    )]
+    pub provider_token_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = lp_token_mint,
+        token::authority = provider,
+        token::token_program = token_program,
+    )]
+    pub provider_lp_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+pub fn withdraw_liquidity(
+    ctx: Context<WithdrawLiquidity>,
+    lp_amount: u64,
+    min_amount_a: u64,
+    min_amount_b: u64,
+) -> Result<()> {
+    require_amm_not_paused(&ctx.accounts.amm, PAUSE_WITHDRAWALS)?;
+    require!(lp_amount > 0, FallError::DepositTooSmall);
+    require!(
+        ctx.accounts.provider_lp_token.amount >= lp_amount,
+        FallError::InsufficientBalance
+    );
+
+    // 1. 按 LP 占比计算可取回数量
+    let (amount_a, amount_b) = withdraw_amounts(
+        lp_amount,
+        ctx.accounts.pool_account_a.amount,
+        ctx.accounts.pool_account_b.amount,
+        ctx.accounts.lp_token_mint.supply,
+    )?;
+    require!(
+        amount_a >= min_amount_a && amount_b >= min_amount_b,
+        FallError::OutputTooSmall
+    );
+
+    // 2. 销毁 LP
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_token_mint.to_account_info(),
+                from: ctx.accounts.provider_lp_token.to_account_info(),
+                authority: ctx.accounts.provider.to_account_info(),
+            },
+        ),
+        lp_amount,
+    )?;
+
+    // 3. 转出 token a / token b
+    let swap_pool_key = ctx.accounts.swap_pool.key();
+    let authority_seeds = &[
+        swap_pool_key.as_ref(),
+        AUTHORITY_SEED,
+        &[ctx.bumps.swap_pool_authority],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+    if amount_a > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program_a,
+            &ctx.accounts.pool_account_a,
+            &ctx.accounts.provider_token_a,
+            &ctx.accounts.mint_a,
+            &ctx.accounts.swap_pool_authority.to_account_info(),
+            signer_seeds,
+            amount_a,
+        )?;
+    }
+    if amount_b > 0 {
+        transfer_tokens(
+            &ctx.accounts.token_program_b,
+            &ctx.accounts.pool_account_b,
+            &ctx.accounts.provider_token_b,
+            &ctx.accounts.mint_b,
+            &ctx.accounts.swap_pool_authority.to_account_info(),
+            signer_seeds,
+            amount_b,
+        )?;
+    }
+
+    Ok(())
+}
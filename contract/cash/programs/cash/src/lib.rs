@@ -2,8 +2,14 @@
 
 use anchor_lang::prelude::*;
 mod constants;
+mod curve;
+mod errors;
+mod events;
 mod instructions;
+mod oracle;
 mod state;
+#[cfg(test)]
+mod test_utils;
 
 declare_id!("EYCdeLWKH7F5JejES1aPvGBqFaT9S1e2roEThq1y9FAR");
 
@@ -17,16 +23,116 @@ pub mod cash {
         instructions::create_amm(ctx, id)
     }
 
-    pub fn create_pool_1(ctx: Context<CreatePool1>) -> Result<()> {
-        instructions::create_pool_1(ctx)
+    pub fn create_pool_1(
+        ctx: Context<CreatePool1>,
+        collateral_ratio: u64,
+        min_collateral_ratio: u64,
+        non_transferable_receipts: bool,
+        debt_ceiling: u64,
+    ) -> Result<()> {
+        instructions::create_pool_1(
+            ctx,
+            collateral_ratio,
+            min_collateral_ratio,
+            non_transferable_receipts,
+            debt_ceiling,
+        )
+    }
+
+    pub fn update_amm_config(
+        ctx: Context<UpdateAmmConfig>,
+        liquidity_fee: u16,
+        protocol_fee_percentage: u16,
+    ) -> Result<()> {
+        instructions::update_amm_config(ctx, liquidity_fee, protocol_fee_percentage)
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    pub fn cancel_admin_proposal(ctx: Context<ProposeAdmin>) -> Result<()> {
+        instructions::cancel_admin_proposal(ctx)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        instructions::set_guardian(ctx, guardian)
+    }
+
+    pub fn set_amm_pause(ctx: Context<SetAmmPause>, pause_flags: u8) -> Result<()> {
+        instructions::set_amm_pause(ctx, pause_flags)
+    }
+
+    pub fn set_pool_pause(ctx: Context<SetPoolPause>, pause_flags: u8) -> Result<()> {
+        instructions::set_pool_pause(ctx, pause_flags)
+    }
+
+    pub fn update_pool_config(ctx: Context<UpdatePoolConfig>, params: PoolConfigParams) -> Result<()> {
+        instructions::update_pool_config(ctx, params)
+    }
+
+    pub fn set_interest_rate_model(
+        ctx: Context<SetInterestRateModel>,
+        base_rate: u64,
+        kink: u64,
+        slope_1: u64,
+        slope_2: u64,
+    ) -> Result<()> {
+        instructions::set_interest_rate_model(ctx, base_rate, kink, slope_1, slope_2)
+    }
+
+    pub fn set_oracle(
+        ctx: Context<SetOracle>,
+        oracle_type: state::OracleType,
+        max_price_age: u64,
+        max_confidence: u64,
+    ) -> Result<()> {
+        instructions::set_oracle(ctx, oracle_type, max_price_age, max_confidence)
+    }
+
+    #[cfg(feature = "mock-oracle")]
+    pub fn set_mock_price(ctx: Context<SetMockPrice>, price: i64, expo: i32, conf: u64) -> Result<()> {
+        instructions::set_mock_price(ctx, price, expo, conf)
+    }
+
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        instructions::collect_protocol_fees(ctx)
+    }
+
+    pub fn liquidate(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
+        instructions::liquidate(ctx, repay_amount)
     }
 
     pub fn lend(ctx: Context<Lend>, user_lending_amount: u64) -> Result<()> {
         instructions::lend(ctx, user_lending_amount)
     }
 
-    pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
-        instructions::redeem(ctx)
+    pub fn redeem(ctx: Context<Redeem>, amount: u64) -> Result<()> {
+        instructions::redeem(ctx, amount)
+    }
+
+    pub fn redeem_all(ctx: Context<Redeem>) -> Result<()> {
+        instructions::redeem_all(ctx)
+    }
+
+    pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+        instructions::flash_borrow(ctx, amount)
+    }
+
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        instructions::flash_repay(ctx)
+    }
+
+    pub fn flash_mint(ctx: Context<FlashMint>, amount: u64) -> Result<()> {
+        instructions::flash_mint(ctx, amount)
+    }
+
+    pub fn flash_mint_repay(ctx: Context<FlashMintRepay>) -> Result<()> {
+        instructions::flash_mint_repay(ctx)
     }
 
     pub fn create_cash_pool(ctx: Context<CreateCashPool>) -> Result<()> {
@@ -37,8 +143,62 @@ pub mod cash {
         instructions::lend_cash(ctx, user_lending_amount)
     }
 
-    pub fn redeem_cash(ctx: Context<RedeemCash>) -> Result<()> {
-        instructions::redeem_cash(ctx)
+    pub fn redeem_cash(ctx: Context<RedeemCash>, amount: u64) -> Result<()> {
+        instructions::redeem_cash(ctx, amount)
+    }
+
+    pub fn create_swap_pool(
+        ctx: Context<CreateSwapPool>,
+        curve_type: state::CurveType,
+        amp: u64,
+    ) -> Result<()> {
+        instructions::create_swap_pool(ctx, curve_type, amp)
+    }
+
+    pub fn ramp_amp(ctx: Context<RampAmp>, target_amp: u64, ramp_stop_timestamp: i64) -> Result<()> {
+        instructions::ramp_amp(ctx, target_amp, ramp_stop_timestamp)
     }
 
+    pub fn stop_ramp_amp(ctx: Context<RampAmp>) -> Result<()> {
+        instructions::stop_ramp_amp(ctx)
+    }
+
+    pub fn deposit_liquidity(
+        ctx: Context<DepositLiquidity>,
+        max_amount_a: u64,
+        max_amount_b: u64,
+        min_lp_amount: u64,
+    ) -> Result<()> {
+        instructions::deposit_liquidity(ctx, max_amount_a, max_amount_b, min_lp_amount)
+    }
+
+    pub fn withdraw_liquidity(
+        ctx: Context<WithdrawLiquidity>,
+        lp_amount: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> Result<()> {
+        instructions::withdraw_liquidity(ctx, lp_amount, min_amount_a, min_amount_b)
+    }
+
+    pub fn swap_exact_in(ctx: Context<SwapExactIn>, amount_in: u64, min_amount_out: u64) -> Result<()> {
+        instructions::swap_exact_in(ctx, amount_in, min_amount_out)
+    }
+
+    pub fn create_psm_pool(
+        ctx: Context<CreatePsmPool>,
+        debt_ceiling: u64,
+        psm_mint_fee: u64,
+        psm_redeem_fee: u64,
+    ) -> Result<()> {
+        instructions::create_psm_pool(ctx, debt_ceiling, psm_mint_fee, psm_redeem_fee)
+    }
+
+    pub fn psm_mint(ctx: Context<PsmSwap>, amount: u64) -> Result<()> {
+        instructions::psm_mint(ctx, amount)
+    }
+
+    pub fn psm_redeem(ctx: Context<PsmSwap>, cash_amount: u64) -> Result<()> {
+        instructions::psm_redeem(ctx, cash_amount)
+    }
 }
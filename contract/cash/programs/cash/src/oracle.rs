@@ -0,0 +1,408 @@
+use anchor_lang::prelude::*;
+use crate::{
+    constants::{CASH_DECIMALS, PRICE_PRECISION, RATIO_PRECISION},
+    errors::FallError,
+    state::{OracleType, Pool},
+};
+#[cfg(feature = "mock-oracle")]
+use crate::state::MockPriceFeed;
+
+/// Pyth oracle program (mainnet-beta)
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+/// Switchboard v2 program
+pub const SWITCHBOARD_PROGRAM_ID: Pubkey = pubkey!("SW1TCH7qEPTdLsDHRgPuMQjbQxKdH2aBStViMFnt64f");
+
+/// Pyth v2 price account layout
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_TIMESTAMP_OFFSET: usize = 96;
+const PYTH_PRICE_OFFSET: usize = 208;
+const PYTH_CONF_OFFSET: usize = 216;
+const PYTH_STATUS_OFFSET: usize = 224;
+
+/// Switchboard v2 aggregator layout (packed, after the 8 byte discriminator)
+const SWITCHBOARD_ROUND_OPEN_TIMESTAMP_OFFSET: usize = 358;
+const SWITCHBOARD_RESULT_OFFSET: usize = 366;
+const SWITCHBOARD_STD_DEVIATION_OFFSET: usize = 386;
+
+/// 预言机原始报价: price * 10^expo
+#[derive(Debug)]
+struct RawPrice {
+    price: i128,
+    conf: u128,
+    expo: i32,
+    publish_time: i64,
+}
+
+/// 读取 pool 配置的预言机, 返回归一化价格:
+/// 1 个 token a 最小单位值多少 cash 最小单位, 以 PRICE_PRECISION 为 1.0
+pub fn get_price(pool: &Pool, oracle: &AccountInfo) -> Result<u128> {
+    let collateral = pool.collateral()?;
+    require_keys_eq!(oracle.key(), collateral.oracle, FallError::InvalidOracleAccount);
+
+    let raw = match collateral.oracle_type {
+        // 未配置预言机时按 1:1 计价
+        OracleType::None => return Ok(PRICE_PRECISION),
+        OracleType::Pyth => read_pyth(oracle)?,
+        OracleType::Switchboard => read_switchboard(oracle)?,
+        #[cfg(feature = "mock-oracle")]
+        OracleType::Mock => read_mock(oracle)?,
+    };
+
+    check_price(pool, &raw, Clock::get()?.unix_timestamp)
+}
+
+/// 校验报价的正负、时效与置信区间, 返回归一化价格
+fn check_price(pool: &Pool, raw: &RawPrice, now: i64) -> Result<u128> {
+    let collateral = pool.collateral()?;
+    require!(raw.price > 0, FallError::InvalidOraclePrice);
+
+    let age = now.saturating_sub(raw.publish_time);
+    require!(
+        age >= 0 && (age as u64) <= collateral.max_price_age,
+        FallError::StaleOraclePrice
+    );
+
+    let price = raw.price as u128;
+    require!(
+        raw.conf
+            .checked_mul(RATIO_PRECISION as u128)
+            .ok_or(FallError::ArithmeticOverflow)?
+            <= price
+                .checked_mul(collateral.max_confidence as u128)
+                .ok_or(FallError::ArithmeticOverflow)?,
+        FallError::OracleConfidenceTooWide
+    );
+
+    normalize_price(price, raw.expo, pool.mint_a_decimals)
+}
+
+/// token a 数量 -> cash 价值, rounded down
+pub fn collateral_value(amount: u64, price: u128) -> Result<u64> {
+    let value = (amount as u128)
+        .checked_mul(price)
+        .ok_or(FallError::ArithmeticOverflow)?
+        / PRICE_PRECISION;
+    u64::try_from(value).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// cash 价值 -> token a 数量, rounded down
+pub fn collateral_for_value(value: u64, price: u128) -> Result<u64> {
+    let amount = (value as u128)
+        .checked_mul(PRICE_PRECISION)
+        .ok_or(FallError::ArithmeticOverflow)?
+        / price;
+    u64::try_from(amount).map_err(|_| error!(FallError::NumberOverflow))
+}
+
+/// price * 10^expo (每个完整 token a 的价格) -> 每个最小单位的 cash 价格 * PRICE_PRECISION
+fn normalize_price(price: u128, expo: i32, mint_decimals: u8) -> Result<u128> {
+    let precision_decimals = PRICE_PRECISION.ilog10() as i32;
+    let exponent = expo + CASH_DECIMALS as i32 + precision_decimals - mint_decimals as i32;
+    let scale = 10u128
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or(FallError::ArithmeticOverflow)?;
+    let normalized = if exponent >= 0 {
+        price.checked_mul(scale).ok_or(FallError::ArithmeticOverflow)?
+    } else {
+        price / scale
+    };
+    require!(normalized > 0, FallError::InvalidOraclePrice);
+    Ok(normalized)
+}
+
+fn read_pyth(oracle: &AccountInfo) -> Result<RawPrice> {
+    // 布局校验挡不住伪造账户, 必须由预言机程序拥有
+    require_keys_eq!(*oracle.owner, PYTH_PROGRAM_ID, FallError::InvalidOracleAccount);
+    let buf = oracle.try_borrow_data()?;
+    let data: &[u8] = &buf;
+    require!(
+        data.len() >= PYTH_STATUS_OFFSET + 4 && read_u32(data, 0) == PYTH_MAGIC,
+        FallError::InvalidOracleAccount
+    );
+    require!(
+        read_u32(data, PYTH_STATUS_OFFSET) == PYTH_STATUS_TRADING,
+        FallError::InvalidOraclePrice
+    );
+
+    Ok(RawPrice {
+        price: read_i64(data, PYTH_PRICE_OFFSET) as i128,
+        conf: read_u64(data, PYTH_CONF_OFFSET) as u128,
+        expo: read_u32(data, PYTH_EXPO_OFFSET) as i32,
+        publish_time: read_i64(data, PYTH_TIMESTAMP_OFFSET),
+    })
+}
+
+fn read_switchboard(oracle: &AccountInfo) -> Result<RawPrice> {
+    require_keys_eq!(*oracle.owner, SWITCHBOARD_PROGRAM_ID, FallError::InvalidOracleAccount);
+    let buf = oracle.try_borrow_data()?;
+    let data: &[u8] = &buf;
+    require!(
+        data.len() >= SWITCHBOARD_STD_DEVIATION_OFFSET + 20,
+        FallError::InvalidOracleAccount
+    );
+
+    // SwitchboardDecimal { mantissa: i128, scale: u32 } = mantissa * 10^-scale
+    let scale = read_u32(data, SWITCHBOARD_RESULT_OFFSET + 16);
+    let std_deviation = read_i128(data, SWITCHBOARD_STD_DEVIATION_OFFSET);
+    let std_scale = read_u32(data, SWITCHBOARD_STD_DEVIATION_OFFSET + 16);
+    require!(std_scale == scale, FallError::InvalidOraclePrice);
+
+    Ok(RawPrice {
+        price: read_i128(data, SWITCHBOARD_RESULT_OFFSET),
+        conf: std_deviation.unsigned_abs(),
+        expo: -(scale as i32),
+        publish_time: read_i64(data, SWITCHBOARD_ROUND_OPEN_TIMESTAMP_OFFSET),
+    })
+}
+
+#[cfg(feature = "mock-oracle")]
+fn read_mock(oracle: &AccountInfo) -> Result<RawPrice> {
+    require_keys_eq!(*oracle.owner, crate::ID, FallError::InvalidOracleAccount);
+    let feed = MockPriceFeed::try_deserialize(&mut &oracle.try_borrow_data()?[..])?;
+
+    Ok(RawPrice {
+        price: feed.price as i128,
+        conf: feed.conf as u128,
+        expo: feed.expo,
+        publish_time: feed.publish_time,
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_i128(data: &[u8], offset: usize) -> i128 {
+    i128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{CollateralPool, PoolState};
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn pool(mint_a_decimals: u8) -> Pool {
+        Pool {
+            mint_a_decimals,
+            state: PoolState::Collateral(CollateralPool {
+                max_price_age: 60,
+                max_confidence: 100, // 1%
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn read_owned(
+        reader: fn(&AccountInfo) -> Result<RawPrice>,
+        owner: Pubkey,
+        data: &[u8],
+    ) -> Result<RawPrice> {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = data.to_vec();
+        let oracle = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        reader(&oracle)
+    }
+
+    fn pyth(data: &[u8]) -> Result<RawPrice> {
+        read_owned(read_pyth, PYTH_PROGRAM_ID, data)
+    }
+
+    fn switchboard(data: &[u8]) -> Result<RawPrice> {
+        read_owned(read_switchboard, SWITCHBOARD_PROGRAM_ID, data)
+    }
+
+    fn pyth_buffer(price: i64, conf: u64, expo: i32, publish_time: i64, status: u32) -> Vec<u8> {
+        let mut data = vec![0u8; PYTH_STATUS_OFFSET + 4];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[PYTH_EXPO_OFFSET..PYTH_EXPO_OFFSET + 4].copy_from_slice(&expo.to_le_bytes());
+        data[PYTH_TIMESTAMP_OFFSET..PYTH_TIMESTAMP_OFFSET + 8].copy_from_slice(&publish_time.to_le_bytes());
+        data[PYTH_PRICE_OFFSET..PYTH_PRICE_OFFSET + 8].copy_from_slice(&price.to_le_bytes());
+        data[PYTH_CONF_OFFSET..PYTH_CONF_OFFSET + 8].copy_from_slice(&conf.to_le_bytes());
+        data[PYTH_STATUS_OFFSET..PYTH_STATUS_OFFSET + 4].copy_from_slice(&status.to_le_bytes());
+        data
+    }
+
+    fn switchboard_buffer(mantissa: i128, std_deviation: i128, scale: u32, round_open: i64) -> Vec<u8> {
+        let mut data = vec![0u8; SWITCHBOARD_STD_DEVIATION_OFFSET + 20];
+        let ts = SWITCHBOARD_ROUND_OPEN_TIMESTAMP_OFFSET;
+        data[ts..ts + 8].copy_from_slice(&round_open.to_le_bytes());
+        let result = SWITCHBOARD_RESULT_OFFSET;
+        data[result..result + 16].copy_from_slice(&mantissa.to_le_bytes());
+        data[result + 16..result + 20].copy_from_slice(&scale.to_le_bytes());
+        let std = SWITCHBOARD_STD_DEVIATION_OFFSET;
+        data[std..std + 16].copy_from_slice(&std_deviation.to_le_bytes());
+        data[std + 16..std + 20].copy_from_slice(&scale.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn pyth_price_is_normalized() {
+        // $150.00000000, 9 位精度的 token: 1 lamport = 150 * 10^6 / 10^9 cash 最小单位
+        let data = pyth_buffer(15_000_000_000, 1_000_000, -8, NOW - 10, PYTH_STATUS_TRADING);
+        let raw = pyth(&data).unwrap();
+        assert_eq!(raw.price, 15_000_000_000);
+        assert_eq!(raw.expo, -8);
+        assert_eq!(raw.publish_time, NOW - 10);
+
+        let price = check_price(&pool(9), &raw, NOW).unwrap();
+        assert_eq!(price, 150 * PRICE_PRECISION / 1000);
+        assert_eq!(collateral_value(1_000_000_000, price).unwrap(), 150_000_000);
+    }
+
+    #[test]
+    fn pyth_rejects_bad_accounts() {
+        let mut data = pyth_buffer(100_000_000, 0, -8, NOW, PYTH_STATUS_TRADING);
+        data[0] ^= 1;
+        assert!(pyth(&data).is_err());
+
+        let data = pyth_buffer(100_000_000, 0, -8, NOW, 0);
+        assert!(pyth(&data).is_err());
+
+        let data = pyth_buffer(100_000_000, 0, -8, NOW, PYTH_STATUS_TRADING);
+        assert!(pyth(&data[..PYTH_STATUS_OFFSET]).is_err());
+
+        let data = pyth_buffer(-1, 0, -8, NOW, PYTH_STATUS_TRADING);
+        let raw = pyth(&data).unwrap();
+        assert!(check_price(&pool(6), &raw, NOW).is_err());
+    }
+
+    #[test]
+    fn oracle_accounts_must_be_owned_by_oracle_program() {
+        // 布局正确但由其他程序创建的账户不能冒充预言机
+        let data = pyth_buffer(100_000_000, 0, -8, NOW, PYTH_STATUS_TRADING);
+        assert_eq!(
+            read_owned(read_pyth, Pubkey::new_unique(), &data).unwrap_err(),
+            FallError::InvalidOracleAccount.into()
+        );
+        assert!(read_owned(read_pyth, SWITCHBOARD_PROGRAM_ID, &data).is_err());
+
+        let data = switchboard_buffer(10_000, 0, 4, NOW);
+        assert_eq!(
+            read_owned(read_switchboard, Pubkey::new_unique(), &data).unwrap_err(),
+            FallError::InvalidOracleAccount.into()
+        );
+        assert!(read_owned(read_switchboard, PYTH_PROGRAM_ID, &data).is_err());
+    }
+
+    #[test]
+    fn pyth_rejects_stale_prices() {
+        let data = pyth_buffer(100_000_000, 0, -8, NOW - 60, PYTH_STATUS_TRADING);
+        let raw = pyth(&data).unwrap();
+        assert!(check_price(&pool(6), &raw, NOW).is_ok());
+        assert_eq!(
+            check_price(&pool(6), &raw, NOW + 1).unwrap_err(),
+            FallError::StaleOraclePrice.into()
+        );
+        // 来自未来的报价同样拒绝
+        assert_eq!(
+            check_price(&pool(6), &raw, NOW - 61).unwrap_err(),
+            FallError::StaleOraclePrice.into()
+        );
+    }
+
+    #[test]
+    fn pyth_rejects_wide_confidence() {
+        let data = pyth_buffer(100_000_000, 1_000_000, -8, NOW, PYTH_STATUS_TRADING);
+        let raw = pyth(&data).unwrap();
+        assert!(check_price(&pool(6), &raw, NOW).is_ok());
+
+        let data = pyth_buffer(100_000_000, 1_000_001, -8, NOW, PYTH_STATUS_TRADING);
+        let raw = pyth(&data).unwrap();
+        assert_eq!(
+            check_price(&pool(6), &raw, NOW).unwrap_err(),
+            FallError::OracleConfidenceTooWide.into()
+        );
+    }
+
+    #[test]
+    fn switchboard_price_is_normalized() {
+        // 1.0005 (scale 4), 6 位精度的 token
+        let data = switchboard_buffer(10_005, 10, 4, NOW - 5);
+        let raw = switchboard(&data).unwrap();
+        assert_eq!(raw.price, 10_005);
+        assert_eq!(raw.conf, 10);
+        assert_eq!(raw.expo, -4);
+        assert_eq!(raw.publish_time, NOW - 5);
+
+        let price = check_price(&pool(6), &raw, NOW).unwrap();
+        assert_eq!(price, 10_005 * PRICE_PRECISION / 10_000);
+    }
+
+    #[test]
+    fn switchboard_rejects_stale_and_wide_prices() {
+        let data = switchboard_buffer(10_000, 0, 4, NOW - 61);
+        let raw = switchboard(&data).unwrap();
+        assert_eq!(
+            check_price(&pool(6), &raw, NOW).unwrap_err(),
+            FallError::StaleOraclePrice.into()
+        );
+
+        let data = switchboard_buffer(10_000, -101, 4, NOW);
+        let raw = switchboard(&data).unwrap();
+        assert_eq!(
+            check_price(&pool(6), &raw, NOW).unwrap_err(),
+            FallError::OracleConfidenceTooWide.into()
+        );
+
+        let mut data = switchboard_buffer(10_000, 0, 4, NOW);
+        let std_scale = SWITCHBOARD_STD_DEVIATION_OFFSET + 16;
+        data[std_scale..std_scale + 4].copy_from_slice(&5u32.to_le_bytes());
+        assert!(switchboard(&data).is_err());
+
+        assert!(switchboard(&data[..SWITCHBOARD_STD_DEVIATION_OFFSET]).is_err());
+    }
+
+    #[test]
+    fn normalize_price_handles_both_exponent_signs() {
+        // expo + 6 + 12 - decimals >= 0
+        assert_eq!(normalize_price(2, 0, 6).unwrap(), 2 * PRICE_PRECISION);
+        // expo 很小时向下取整, 结果为 0 则拒绝
+        assert_eq!(normalize_price(123_456_789, -20, 6).unwrap(), 1);
+        assert!(normalize_price(99_999_999, -20, 6).is_err());
+        assert!(normalize_price(u128::MAX, 10, 0).is_err());
+    }
+
+    #[cfg(feature = "mock-oracle")]
+    #[test]
+    fn mock_price_feed_account() {
+        let feed = MockPriceFeed {
+            amm: Pubkey::new_unique(),
+            price: 250,
+            expo: -2,
+            conf: 1,
+            publish_time: NOW,
+        };
+        let mut data = Vec::with_capacity(MockPriceFeed::LEN);
+        feed.try_serialize(&mut data).unwrap();
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let owner = crate::ID;
+        let oracle = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        let raw = read_mock(&oracle).unwrap();
+        assert_eq!(raw.price, 250);
+        assert_eq!(raw.expo, -2);
+        assert_eq!(raw.publish_time, NOW);
+        assert_eq!(check_price(&pool(6), &raw, NOW).unwrap(), 2_500_000_000_000);
+
+        // 非本程序拥有的账户不能冒充 mock 预言机
+        let mut lamports = 0;
+        let other = Pubkey::new_unique();
+        let oracle = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &other, false, 0);
+        assert!(read_mock(&oracle).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::errors::FallError;
 
 #[account]
 #[derive(Default)]
@@ -9,16 +10,25 @@ pub struct Amm {
     /// Account that has admin authority over the AMM
     pub admin: Pubkey,
 
-    /// liquidity fee percentage: 100000 = 100%
+    /// liquidity fee percentage: 10000 = 100%
     pub liquidity_fee: u16, 
 
     /// Protocol fee percentage of the liquidity fee (0-100)
     /// e.g., 10000 means 100% of liquidity fee goes to protocol
     pub protocol_fee_percentage: u16,
+
+    /// Proposed admin, must sign accept_admin to take over
+    pub pending_admin: Pubkey,
+
+    /// Account allowed to pause, but not unpause, the AMM and its pools
+    pub guardian: Pubkey,
+
+    /// 全局暂停标志位: PAUSE_DEPOSITS | PAUSE_WITHDRAWALS | PAUSE_MINTING
+    pub pause_flags: u8,
 }
 
 impl Amm {
-    pub const LEN: usize = 8 + 32 + 32 + 2 + 2;
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 2 + 32 + 32 + 1;
 }
 
 #[account]
@@ -30,14 +40,345 @@ pub struct Pool {
     /// Mint of token A
     pub mint_a: Pubkey,
 
-    /// 借贷池中token a的数量
+    /// 借贷池中token a的数量 (抵押物总量, 含留在池子的闪电贷手续费)
     pub token_a_amount :u64,
-    /// 借贷池中token b的数量
+    /// 借贷池中token b的数量: 抵押池为未偿还的 cash 本金, cash 池为质押中的 cash 总量,
+    /// PSM 池为经 PSM 铸造且尚未赎回的 cash
     pub token_b_amount :u64,
 
-    pub pool_type :u64,
+    /// Decimals of token A
+    pub mint_a_decimals: u8,
+
+    /// 池子暂停标志位: PAUSE_DEPOSITS | PAUSE_WITHDRAWALS | PAUSE_MINTING
+    pub pause_flags: u8,
+
+    /// 池子类型及该类型专属的参数, 每个指令只接受对应类型的池子
+    pub state: PoolState,
 }
 
 impl Pool {
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8+ 8;
-}
\ No newline at end of file
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1 + 1 + PoolState::LEN;
+
+    pub fn kind(&self) -> PoolKind {
+        match self.state {
+            PoolState::Collateral(_) => PoolKind::Collateral,
+            PoolState::Cash(_) => PoolKind::Cash,
+            PoolState::Psm(_) => PoolKind::Psm,
+        }
+    }
+
+    pub fn collateral(&self) -> Result<&CollateralPool> {
+        match &self.state {
+            PoolState::Collateral(collateral) => Ok(collateral),
+            _ => err!(FallError::InvalidPoolKind),
+        }
+    }
+
+    pub fn collateral_mut(&mut self) -> Result<&mut CollateralPool> {
+        match &mut self.state {
+            PoolState::Collateral(collateral) => Ok(collateral),
+            _ => err!(FallError::InvalidPoolKind),
+        }
+    }
+
+    pub fn psm(&self) -> Result<&PsmPool> {
+        match &self.state {
+            PoolState::Psm(psm) => Ok(psm),
+            _ => err!(FallError::InvalidPoolKind),
+        }
+    }
+
+    pub fn psm_mut(&mut self) -> Result<&mut PsmPool> {
+        match &mut self.state {
+            PoolState::Psm(psm) => Ok(psm),
+            _ => err!(FallError::InvalidPoolKind),
+        }
+    }
+
+    /// 存款额度, 仅抵押池与 cash 池有
+    pub fn deposit_limits(&self) -> Result<&DepositLimits> {
+        match &self.state {
+            PoolState::Collateral(collateral) => Ok(&collateral.deposit_limits),
+            PoolState::Cash(cash) => Ok(&cash.deposit_limits),
+            PoolState::Psm(_) => err!(FallError::InvalidPoolKind),
+        }
+    }
+
+    pub fn deposit_limits_mut(&mut self) -> Result<&mut DepositLimits> {
+        match &mut self.state {
+            PoolState::Collateral(collateral) => Ok(&mut collateral.deposit_limits),
+            PoolState::Cash(cash) => Ok(&mut cash.deposit_limits),
+            PoolState::Psm(_) => err!(FallError::InvalidPoolKind),
+        }
+    }
+}
+
+// 账户按最大的变体分配空间, 装箱并不能节省链上存储
+#[allow(clippy::large_enum_variant)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub enum PoolState {
+    /// 抵押 token a 铸造 cash (create_pool_1)
+    Collateral(CollateralPool),
+    /// 质押 cash 换取 scash (create_cash_pool)
+    Cash(CashPool),
+    /// 白名单稳定币 1:1 兑换 cash (create_psm_pool)
+    Psm(PsmPool),
+}
+
+impl PoolState {
+    /// 1 字节变体标签 + 最大的变体
+    pub const LEN: usize = 1 + CollateralPool::LEN;
+}
+
+impl Default for PoolState {
+    fn default() -> Self {
+        PoolState::Collateral(CollateralPool::default())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct CollateralPool {
+    /// 铸造 cash 时的抵押率: 12000 = 120%
+    pub collateral_ratio: u64,
+
+    /// 仓位允许的最低抵押率: 11000 = 110%
+    pub min_collateral_ratio: u64,
+
+    /// 累计借款指数, INDEX_PRECISION = 1.0
+    pub borrow_index: u128,
+
+    /// 以 borrow_index 归一化的 cash 总债务
+    pub total_scaled_debt: u128,
+
+    /// Unix timestamp of the last interest accrual
+    pub last_accrual_timestamp: i64,
+
+    /// 年化基础利率: 10000 = 100%
+    pub base_rate: u64,
+
+    /// 利率曲线拐点利用率: 8000 = 80%
+    pub kink: u64,
+
+    /// 拐点前利率斜率
+    pub slope_1: u64,
+
+    /// 拐点后利率斜率
+    pub slope_2: u64,
+
+    /// 清算奖励: 500 = 5%
+    pub liquidation_bonus: u64,
+
+    /// 单次清算最多偿还的债务比例: 5000 = 50%
+    pub close_factor: u64,
+
+    /// token a 价格预言机账户
+    pub oracle: Pubkey,
+
+    pub oracle_type: OracleType,
+
+    /// 预言机价格最大有效期 (秒)
+    pub max_price_age: u64,
+
+    /// 置信区间 / 价格 上限: 100 = 1%
+    pub max_confidence: u64,
+
+    pub deposit_limits: DepositLimits,
+
+    /// 铸造给 lender 的 cash 是否冻结在钱包中
+    pub freeze_cash: bool,
+
+    /// lending receipt 是否为 Token-2022 NonTransferable mint, 直接铸造到 lender 钱包
+    pub non_transferable_receipts: bool,
+
+    /// 池子未偿还 cash (含利息) 上限, 0 = 不限制
+    pub debt_ceiling: u64,
+
+    /// token a 闪电贷手续费: 9 = 0.09%, 按 protocol_fee_percentage 分给协议金库, 其余留在池子
+    pub flash_loan_fee: u64,
+
+    /// 进行中的 token a 闪电贷数量, 同一交易内 flash_repay 后清零
+    pub flash_loan_amount: u64,
+
+    /// 单笔 cash 闪电铸造上限, 0 = 不允许闪电铸造
+    pub flash_mint_cap: u64,
+
+    /// cash 闪电铸造手续费: 9 = 0.09%, 手续费进入 cash 池
+    pub flash_mint_fee: u64,
+
+    /// 进行中的 cash 闪电铸造数量, 同一交易内 flash_mint_repay 后清零
+    pub flash_mint_amount: u64,
+}
+
+impl CollateralPool {
+    pub const LEN: usize = 8 + 8 + 16 + 16 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 1 + 8 + 8
+        + DepositLimits::LEN + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 8;
+
+    /// lending receipt 的持有者: NonTransferable 时为 lender 本人, 否则为 lender_authority PDA
+    pub fn receipt_owner(&self, lender: Pubkey, lender_authority: Pubkey) -> Pubkey {
+        if self.non_transferable_receipts {
+            lender
+        } else {
+            lender_authority
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct CashPool {
+    pub deposit_limits: DepositLimits,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct PsmPool {
+    /// 经 PSM 铸造的 cash 上限, 0 = 不限制
+    pub debt_ceiling: u64,
+
+    /// PSM 存入稳定币铸造 cash 的手续费: 10 = 0.1%, 以 cash 计入协议金库
+    pub psm_mint_fee: u64,
+
+    /// PSM 销毁 cash 赎回稳定币的手续费: 10 = 0.1%, 以 cash 计入协议金库
+    pub psm_redeem_fee: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct DepositLimits {
+    /// 单笔最小存款
+    pub min_deposit: u64,
+
+    /// 单个仓位存款上限, 0 = 不限制
+    pub max_position_size: u64,
+
+    /// 池子总存款上限, 0 = 不限制
+    pub deposit_cap: u64,
+}
+
+impl DepositLimits {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+#[account]
+#[derive(Default)]
+pub struct SwapPool {
+    /// Primary key of the AMM
+    pub amm: Pubkey,
+
+    /// Mint of token A, mint_a < mint_b
+    pub mint_a: Pubkey,
+
+    /// Mint of token B
+    pub mint_b: Pubkey,
+
+    /// 定价曲线
+    pub curve_type: CurveType,
+
+    /// StableSwap 放大系数, 在 [ramp_start_timestamp, ramp_stop_timestamp] 内
+    /// 从 initial_amp 线性过渡到 target_amp
+    pub initial_amp: u64,
+    pub target_amp: u64,
+    pub ramp_start_timestamp: i64,
+    pub ramp_stop_timestamp: i64,
+}
+
+impl SwapPool {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 8;
+
+    /// 当前时刻的放大系数
+    pub fn current_amp(&self, now: i64) -> u64 {
+        if now >= self.ramp_stop_timestamp || self.ramp_stop_timestamp <= self.ramp_start_timestamp {
+            return self.target_amp;
+        }
+        let elapsed = now.saturating_sub(self.ramp_start_timestamp).max(0) as u128;
+        let duration = (self.ramp_stop_timestamp - self.ramp_start_timestamp) as u128;
+        let (initial, target) = (self.initial_amp as u128, self.target_amp as u128);
+        let amp = if target > initial {
+            initial + (target - initial) * elapsed / duration
+        } else {
+            initial - (initial - target) * elapsed / duration
+        };
+        amp as u64
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum CurveType {
+    /// x * y = k
+    #[default]
+    ConstantProduct,
+    /// 放大系数 A 的 StableSwap 不变量, 用于锚定同一价格的 token
+    StableSwap,
+}
+
+/// PoolState 的变体标签, 用于账户约束
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PoolKind {
+    Collateral,
+    Cash,
+    Psm,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum OracleType {
+    /// 未配置预言机, token a 与 cash 按 1:1 计价
+    #[default]
+    None,
+    Pyth,
+    Switchboard,
+    /// Admin-set feed for localnet
+    #[cfg(feature = "mock-oracle")]
+    Mock,
+}
+
+#[account]
+#[derive(Default)]
+pub struct LenderPosition {
+    /// Pool the position belongs to
+    pub pool: Pubkey,
+
+    /// Owner of the position
+    pub lender: Pubkey,
+
+    /// token a 抵押数量
+    pub collateral_amount: u64,
+
+    /// 已铸造的 cash 本金
+    pub cash_debt: u64,
+
+    /// 以 borrow_index 归一化的 cash 债务 (本金 + 利息)
+    pub scaled_debt: u128,
+
+    /// Unix timestamp of the first lend
+    pub open_timestamp: i64,
+
+    /// Unix timestamp of the last lend/redeem
+    pub last_update_timestamp: i64,
+
+    pub bump: u8,
+}
+
+impl LenderPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 16 + 8 + 8 + 1;
+}
+
+#[cfg(feature = "mock-oracle")]
+#[account]
+#[derive(Default)]
+pub struct MockPriceFeed {
+    /// Primary key of the AMM
+    pub amm: Pubkey,
+
+    /// 价格 = price * 10^expo
+    pub price: i64,
+
+    pub expo: i32,
+
+    /// 置信区间, 与 price 同精度
+    pub conf: u64,
+
+    /// Unix timestamp of the last update
+    pub publish_time: i64,
+}
+
+#[cfg(feature = "mock-oracle")]
+impl MockPriceFeed {
+    pub const LEN: usize = 8 + 32 + 8 + 4 + 8 + 8;
+}
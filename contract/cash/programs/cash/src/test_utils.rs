@@ -0,0 +1,365 @@
+//! 单元测试用的最小运行时: 在本地直接调用程序入口, token program 的 CPI 交给
+//! spl-token / spl-token-2022 的 processor 执行
+use std::{cell::Cell, collections::BTreeMap, sync::Once};
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        program_option::COption,
+        program_pack::Pack,
+        program_stubs::{self, SyscallStubs},
+    },
+    system_program, AccountSerialize, Discriminator,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token::spl_token,
+    token_2022::spl_token_2022,
+};
+
+thread_local! {
+    static NOW: Cell<i64> = const { Cell::new(1_700_000_000) };
+}
+
+struct TestStubs;
+
+impl SyscallStubs for TestStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: NOW.with(|cell| cell.get()),
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let mut accounts = Vec::with_capacity(instruction.accounts.len());
+        for meta in &instruction.accounts {
+            let mut info = account_infos
+                .iter()
+                .find(|info| *info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?
+                .clone();
+            if meta.is_signer && !info.is_signer {
+                let signed = signers_seeds.iter().any(|seeds| {
+                    Pubkey::create_program_address(seeds, &crate::ID) == Ok(meta.pubkey)
+                });
+                if !signed {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                info.is_signer = true;
+            }
+            accounts.push(info);
+        }
+
+        if instruction.program_id == system_program::ID {
+            process_system(&accounts, &instruction.data)
+        } else if instruction.program_id == associated_token::ID {
+            process_associated_token(&accounts)
+        } else if instruction.program_id == spl_token_2022::ID {
+            spl_token_2022::processor::Processor::process(
+                &instruction.program_id,
+                &accounts,
+                &instruction.data,
+            )
+        } else if instruction.program_id == spl_token::ID {
+            spl_token::processor::Processor::process(
+                &instruction.program_id,
+                &accounts,
+                &instruction.data,
+            )
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// 替换账户数据. 测试账户的缓冲区长度固定, 新分配的空间直接泄漏, 由 Bank 在指令结束后拷回
+fn set_data(info: &AccountInfo, space: usize) {
+    *info.data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let mut from = from.try_borrow_mut_lamports()?;
+    **from = from.checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+/// system program 中用到的 CreateAccount / Assign / Transfer / Allocate
+fn process_system(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let tag = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    match tag {
+        0 => {
+            let (from, to) = (&accounts[0], &accounts[1]);
+            if to.lamports() > 0 || *to.owner != system_program::ID || !to.data_is_empty() {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            move_lamports(from, to, read_u64(data, 4))?;
+            set_data(to, read_u64(data, 12) as usize);
+            to.assign(&Pubkey::try_from(&data[20..52]).unwrap());
+            Ok(())
+        }
+        1 => {
+            accounts[0].assign(&Pubkey::try_from(&data[4..36]).unwrap());
+            Ok(())
+        }
+        2 => move_lamports(&accounts[0], &accounts[1], read_u64(data, 4)),
+        8 => {
+            if !accounts[0].data_is_empty() {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            set_data(&accounts[0], read_u64(data, 4) as usize);
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// associated token program 的 Create: [payer, ata, owner, mint, system, token program]
+fn process_associated_token(accounts: &[AccountInfo]) -> ProgramResult {
+    let (payer, ata, owner, mint, token_program) =
+        (&accounts[0], &accounts[1], &accounts[2], &accounts[3], &accounts[5]);
+    let space = spl_token_2022::state::Account::LEN;
+    move_lamports(payer, ata, Rent::default().minimum_balance(space))?;
+    set_data(ata, space);
+    ata.assign(token_program.key);
+    let initialize = spl_token_2022::instruction::initialize_account3(
+        token_program.key,
+        ata.key,
+        mint.key,
+        owner.key,
+    )?;
+    let accounts = [ata.clone(), mint.clone()];
+    if *token_program.key == spl_token::ID {
+        spl_token::processor::Processor::process(token_program.key, &accounts, &initialize.data)
+    } else {
+        spl_token_2022::processor::Processor::process(token_program.key, &accounts, &initialize.data)
+    }
+}
+
+struct TestAccount {
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    executable: bool,
+}
+
+/// 账户集合, 每次 process 只对本集合生效
+pub struct Bank {
+    accounts: BTreeMap<Pubkey, TestAccount>,
+}
+
+fn install_stubs() {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    });
+}
+
+/// 设置 Clock sysvar 返回的 unix_timestamp (当前线程)
+pub fn set_now(now: i64) {
+    install_stubs();
+    NOW.with(|cell| cell.set(now));
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        install_stubs();
+
+        let mut bank = Bank { accounts: BTreeMap::new() };
+        for program in [
+            system_program::ID,
+            spl_token::ID,
+            spl_token_2022::ID,
+            associated_token::ID,
+            crate::ID,
+        ] {
+            bank.accounts.insert(
+                program,
+                TestAccount {
+                    lamports: 1,
+                    data: vec![],
+                    owner: Pubkey::default(),
+                    executable: true,
+                },
+            );
+        }
+        bank
+    }
+
+    pub fn set_raw(&mut self, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        self.accounts.insert(
+            key,
+            TestAccount {
+                lamports: 1_000_000_000,
+                data,
+                owner,
+                executable: false,
+            },
+        );
+    }
+
+    /// 写入本程序的 Anchor 账户
+    pub fn set_account<T: AccountSerialize + Discriminator>(&mut self, key: Pubkey, account: &T, space: usize) {
+        let mut data = Vec::with_capacity(space);
+        account.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        self.set_raw(key, crate::ID, data);
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        T::try_deserialize(&mut &self.accounts[key].data[..]).unwrap()
+    }
+
+    /// 系统账户, 用作签名者与 payer
+    pub fn set_wallet(&mut self, key: Pubkey) {
+        self.set_raw(key, system_program::ID, vec![]);
+    }
+
+    pub fn set_mint(&mut self, key: Pubkey, token_program: Pubkey, authority: Pubkey, supply: u64, decimals: u8) {
+        let mint = spl_token_2022::state::Mint {
+            mint_authority: COption::Some(authority),
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::Some(authority),
+        };
+        let mut data = vec![0; spl_token_2022::state::Mint::LEN];
+        mint.pack_into_slice(&mut data);
+        self.set_raw(key, token_program, data);
+    }
+
+    /// 创建 ATA 并返回地址
+    pub fn set_ata(&mut self, owner: Pubkey, mint: Pubkey, token_program: Pubkey, amount: u64) -> Pubkey {
+        let key = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+        let account = spl_token_2022::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token_2022::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0; spl_token_2022::state::Account::LEN];
+        account.pack_into_slice(&mut data);
+        self.set_raw(key, token_program, data);
+        key
+    }
+
+    /// 写入 instructions sysvar, current 为正在执行的指令下标
+    #[allow(deprecated)]
+    pub fn set_instructions(&mut self, instructions: &[Instruction], current: u16) {
+        use anchor_lang::solana_program::sysvar::{
+            self,
+            instructions::{construct_instructions_data, BorrowedAccountMeta, BorrowedInstruction},
+        };
+
+        let borrowed: Vec<BorrowedInstruction> = instructions
+            .iter()
+            .map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: ix
+                    .accounts
+                    .iter()
+                    .map(|meta| BorrowedAccountMeta {
+                        pubkey: &meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: &ix.data,
+            })
+            .collect();
+        let mut data = construct_instructions_data(&borrowed);
+        let len = data.len();
+        data[len - 2..].copy_from_slice(&current.to_le_bytes());
+        self.set_raw(sysvar::instructions::ID, sysvar::ID, data);
+    }
+
+    pub fn token_amount(&self, key: &Pubkey) -> u64 {
+        spl_token_2022::state::Account::unpack_from_slice(&self.accounts[key].data).unwrap().amount
+    }
+
+    pub fn mint_supply(&self, key: &Pubkey) -> u64 {
+        spl_token_2022::state::Mint::unpack_from_slice(&self.accounts[key].data).unwrap().supply
+    }
+
+    /// 以 metas 的顺序执行一条本程序指令
+    pub fn process(&mut self, metas: Vec<AccountMeta>, data: Vec<u8>) -> ProgramResult {
+        // 未写入的地址 (如只读的 PDA authority) 视为空的系统账户
+        for meta in &metas {
+            self.accounts.entry(meta.pubkey).or_insert_with(|| TestAccount {
+                lamports: 0,
+                data: vec![],
+                owner: system_program::ID,
+                executable: false,
+            });
+        }
+        let is_signer = |key: &Pubkey| metas.iter().any(|meta| meta.pubkey == *key && meta.is_signer);
+        let is_writable = |key: &Pubkey| metas.iter().any(|meta| meta.pubkey == *key && meta.is_writable);
+        let infos: BTreeMap<Pubkey, AccountInfo> = self
+            .accounts
+            .iter_mut()
+            .filter(|(key, _)| metas.iter().any(|meta| meta.pubkey == **key))
+            .map(|(key, account)| {
+                let info = AccountInfo::new(
+                    key,
+                    is_signer(key),
+                    is_writable(key),
+                    &mut account.lamports,
+                    &mut account.data,
+                    &account.owner,
+                    account.executable,
+                    0,
+                );
+                (*key, info)
+            })
+            .collect();
+        let accounts = metas
+            .iter()
+            .map(|meta| infos.get(&meta.pubkey).cloned().ok_or(ProgramError::NotEnoughAccountKeys))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let result = crate::entry(&crate::ID, &accounts, &data);
+
+        // 拷回新分配的账户数据与 owner
+        let updates: Vec<(Pubkey, Vec<u8>, Pubkey)> = infos
+            .iter()
+            .map(|(key, info)| (*key, info.data.borrow().to_vec(), *info.owner))
+            .collect();
+        for (key, data, owner) in updates {
+            let account = self.accounts.get_mut(&key).unwrap();
+            account.data = data;
+            account.owner = owner;
+        }
+        result
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts[key].lamports
+    }
+
+    pub fn owner(&self, key: &Pubkey) -> Pubkey {
+        self.accounts[key].owner
+    }
+}